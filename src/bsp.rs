@@ -0,0 +1,111 @@
+use rand::Rng;
+
+use crate::builder::{BuildData, MapBuilder};
+use crate::game::GameRng;
+use crate::map::{
+    create_h_tunnel, create_room, create_v_tunnel, is_connected, Map, Rect, Tile,
+};
+
+// parameters for the BSP generator
+const MIN_LEAF_SIZE: i32 = 8; // no part of the map is split smaller than this
+const MAX_LEAF_SIZE: i32 = 16; // anything bigger than this always gets split
+const ROOM_MIN_SIZE: i32 = 6;
+
+/// Split the map in two again and again (a binary space partition), put a room
+/// in each of the parts, and join the two halves of every split with a tunnel.
+/// This spreads the rooms evenly over the whole map, and since every split is
+/// joined, every room can be reached.
+pub struct Bsp;
+
+impl MapBuilder for Bsp {
+    fn build(&self, data: &mut BuildData, rng: &mut GameRng) {
+        let width = data.map.len() as i32;
+        let height = data.map[0].len() as i32;
+        // the last row and column are left out, so the rooms never reach the edge
+        let whole_map = Rect::new(0, 0, width - 1, height - 1);
+        loop {
+            let mut map = vec![vec![Tile::wall(); height as usize]; width as usize];
+            let rooms = split(whole_map, &mut map, rng);
+            // the tree should always join everything, but a map with a part the
+            // player can never get to is not worth the risk
+            if is_connected(&map, rooms[0].center()) {
+                data.map = map;
+                data.rooms = rooms;
+                return;
+            }
+        }
+    }
+}
+
+/// Fill `area` with rooms, splitting it first if it's big enough, and return
+/// the rooms that were made in it. The two halves of a split are joined by a
+/// tunnel between their closest rooms.
+fn split(area: Rect, map: &mut Map, rng: &mut GameRng) -> Vec<Rect> {
+    let width = area.x2 - area.x1;
+    let height = area.y2 - area.y1;
+    let can_split_x = width >= MIN_LEAF_SIZE * 2;
+    let can_split_y = height >= MIN_LEAF_SIZE * 2;
+    let too_big = width > MAX_LEAF_SIZE || height > MAX_LEAF_SIZE;
+
+    if !too_big || !(can_split_x || can_split_y) {
+        // this is a leaf: carve a room of random size somewhere inside it
+        let w = rng.gen_range(ROOM_MIN_SIZE.min(width)..=width);
+        let h = rng.gen_range(ROOM_MIN_SIZE.min(height)..=height);
+        let x = rng.gen_range(area.x1..=area.x2 - w);
+        let y = rng.gen_range(area.y1..=area.y2 - h);
+        let room = Rect::new(x, y, w, h);
+        create_room(room, map);
+        return vec![room];
+    }
+
+    // split across the longer side, so the parts don't end up as long strips
+    let split_x = if can_split_x && can_split_y {
+        width >= height
+    } else {
+        can_split_x
+    };
+    let (first, second) = if split_x {
+        let at = rng.gen_range(area.x1 + MIN_LEAF_SIZE..=area.x2 - MIN_LEAF_SIZE);
+        (
+            Rect { x2: at, ..area },
+            Rect { x1: at, ..area },
+        )
+    } else {
+        let at = rng.gen_range(area.y1 + MIN_LEAF_SIZE..=area.y2 - MIN_LEAF_SIZE);
+        (
+            Rect { y2: at, ..area },
+            Rect { y1: at, ..area },
+        )
+    };
+
+    let mut rooms = split(first, map, rng);
+    let second_rooms = split(second, map, rng);
+
+    // join the closest pair of rooms across the split
+    let distance = |a: &Rect, b: &Rect| {
+        let ((ax, ay), (bx, by)) = (a.center(), b.center());
+        (ax - bx).pow(2) + (ay - by).pow(2)
+    };
+    let mut closest = (rooms[0], second_rooms[0]);
+    for a in &rooms {
+        for b in &second_rooms {
+            if distance(a, b) < distance(&closest.0, &closest.1) {
+                closest = (*a, *b);
+            }
+        }
+    }
+    let ((prev_x, prev_y), (new_x, new_y)) = (closest.0.center(), closest.1.center());
+    // toss a coin (random bool value -- either true or false)
+    if rng.gen() {
+        // first move horizontally, then vertically
+        create_h_tunnel(prev_x, new_x, prev_y, map);
+        create_v_tunnel(prev_y, new_y, new_x, map);
+    } else {
+        // first move vertically, then horizontally
+        create_v_tunnel(prev_y, new_y, prev_x, map);
+        create_h_tunnel(prev_x, new_x, new_y, map);
+    }
+
+    rooms.extend(second_rooms);
+    rooms
+}
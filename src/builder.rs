@@ -0,0 +1,377 @@
+use rand::Rng;
+
+use crate::game::GameRng;
+use crate::map::{distances_from, is_connected, place_objects, Map, Rect, Tile, TileKind};
+use crate::monsters::MonsterTemplate;
+use crate::object::Object;
+
+// monsters and items are placed in maps without rooms (like caves) as if
+// they were split into squares this big
+const SPAWN_AREA_SIZE: i32 = 8;
+// percent of doorways that get a door
+const DOOR_CHANCE: u32 = 50;
+// parameters for pools and rubble
+const MAX_POOLS: u32 = 3;
+const POOL_MIN_SIZE: usize = 4;
+const POOL_MAX_SIZE: usize = 14;
+const LAVA_MIN_LEVEL: u32 = 4; // pools can be lava from this dungeon level on
+const LAVA_CHANCE: u32 = 35; // percent of pools that are lava, when they can be
+const RUBBLE_CHANCE: u32 = 2; // percent of floor tiles covered in rubble
+
+/// A map while it's being built, along with everything the passes so far
+/// have worked out about it.
+pub struct BuildData {
+    pub map: Map,
+    /// the dungeon level being built
+    pub level: u32,
+    /// the rooms laid out, if the layout has any (caves don't)
+    pub rooms: Vec<Rect>,
+    /// the gaps in room walls where corridors come in
+    pub doorways: Vec<(i32, i32)>,
+    /// where the player arrives
+    pub start: Option<(i32, i32)>,
+    /// where the stairs down go
+    pub exit: Option<(i32, i32)>,
+    /// the monsters and items to put on the map (but not the player or the stairs)
+    pub spawns: Vec<Object>,
+}
+
+impl BuildData {
+    /// a map of solid rock, ready for a base layout to be carved into it
+    pub fn new(width: i32, height: i32, level: u32) -> Self {
+        BuildData {
+            map: vec![vec![Tile::wall(); height as usize]; width as usize],
+            level,
+            rooms: vec![],
+            doorways: vec![],
+            start: None,
+            exit: None,
+            spawns: vec![],
+        }
+    }
+
+    /// a map drawn as rows of text, each tile as its glyph (`#` for a wall,
+    /// `~` for water...) and anything else as floor
+    pub fn from_ascii(rows: &[&str], level: u32) -> Self {
+        let mut data = BuildData::new(rows[0].len() as i32, rows.len() as i32, level);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, glyph) in row.chars().enumerate() {
+                let kind = TileKind::from_glyph(glyph).unwrap_or(TileKind::Floor);
+                data.map[x][y] = Tile::new(kind);
+            }
+        }
+        data
+    }
+
+    /// Draw the map as rows of text: each tile as its glyph (`#` for walls,
+    /// `.` for floor...), `+` for doorways, `@` for the start, `<` for the exit
+    /// and each spawned object as its own glyph. Handy to see (and test) what
+    /// a pass did.
+    pub fn to_ascii(&self) -> String {
+        let width = self.map.len();
+        let height = self.map[0].len();
+        let mut rows: Vec<Vec<char>> = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| self.map[x][y].kind.glyph())
+                    .collect()
+            })
+            .collect();
+        let mut mark = |(x, y): (i32, i32), glyph: char| rows[y as usize][x as usize] = glyph;
+        for &doorway in &self.doorways {
+            mark(doorway, '+');
+        }
+        for object in &self.spawns {
+            mark(object.pos(), object.char);
+        }
+        if let Some(start) = self.start {
+            mark(start, '@');
+        }
+        if let Some(exit) = self.exit {
+            mark(exit, '<');
+        }
+        rows.iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// One pass of map generation: laying out the base map, decorating rooms,
+/// placing the start, the exit or the spawns... Passes are chained together
+/// in a `BuilderChain`, each one working on what the ones before it left.
+pub trait MapBuilder {
+    fn build(&self, data: &mut BuildData, rng: &mut GameRng);
+}
+
+/// A list of passes run one after the other to build a map.
+pub struct BuilderChain<'a> {
+    passes: Vec<Box<dyn MapBuilder + 'a>>,
+}
+
+impl<'a> BuilderChain<'a> {
+    /// start a chain from the pass that lays out the base map
+    pub fn new(base: impl MapBuilder + 'a) -> Self {
+        BuilderChain {
+            passes: vec![Box::new(base)],
+        }
+    }
+
+    /// add a pass to run after the ones already in the chain
+    pub fn with(mut self, pass: impl MapBuilder + 'a) -> Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    /// run every pass, in order, on a map of solid rock
+    pub fn build(&self, width: i32, height: i32, level: u32, rng: &mut GameRng) -> BuildData {
+        let mut data = BuildData::new(width, height, level);
+        for pass in &self.passes {
+            pass.build(&mut data, rng);
+        }
+        data
+    }
+}
+
+/// Put pillars in the corners of big rooms, now and then.
+pub struct Pillars;
+
+impl MapBuilder for Pillars {
+    fn build(&self, data: &mut BuildData, rng: &mut GameRng) {
+        for room in &data.rooms {
+            // the room needs to be big enough to walk all the way around the pillars
+            if room.x2 - room.x1 < 8 || room.y2 - room.y1 < 8 || !rng.gen::<bool>() {
+                continue;
+            }
+            for (x, y) in [
+                (room.x1 + 2, room.y1 + 2),
+                (room.x2 - 2, room.y1 + 2),
+                (room.x1 + 2, room.y2 - 2),
+                (room.x2 - 2, room.y2 - 2),
+            ] {
+                data.map[x as usize][y as usize] = Tile::wall();
+            }
+        }
+    }
+}
+
+/// Flood a few patches of floor with water (or lava, deeper down) and scatter
+/// some rubble, keeping the start and the exit clear. Lava never cuts off part
+/// of the map: a pool that would is made water instead.
+pub struct Terrain;
+
+impl MapBuilder for Terrain {
+    fn build(&self, data: &mut BuildData, rng: &mut GameRng) {
+        let width = data.map.len() as i32;
+        let height = data.map[0].len() as i32;
+        let keep_clear = |data: &BuildData, tile: (i32, i32)| {
+            data.start == Some(tile) || data.exit == Some(tile)
+        };
+
+        for _ in 0..rng.gen_range(0..=MAX_POOLS) {
+            let origin = (rng.gen_range(0..width), rng.gen_range(0..height));
+            if data.map[origin.0 as usize][origin.1 as usize].kind != TileKind::Floor
+                || keep_clear(data, origin)
+            {
+                continue;
+            }
+            // grow the pool out from its origin, one random neighbour at a time
+            let size = rng.gen_range(POOL_MIN_SIZE..=POOL_MAX_SIZE);
+            let mut pool = vec![origin];
+            for _ in 0..size * 4 {
+                if pool.len() >= size {
+                    break;
+                }
+                let (x, y) = pool[rng.gen_range(0..pool.len())];
+                let (dx, dy) = [(1, 0), (-1, 0), (0, 1), (0, -1)][rng.gen_range(0..4)];
+                let next = (x + dx, y + dy);
+                if next.0 < 0 || next.1 < 0 || next.0 >= width || next.1 >= height {
+                    continue;
+                }
+                if data.map[next.0 as usize][next.1 as usize].kind == TileKind::Floor
+                    && !keep_clear(data, next)
+                    && !pool.contains(&next)
+                {
+                    pool.push(next);
+                }
+            }
+
+            let lava = data.level >= LAVA_MIN_LEVEL
+                && rng.gen_range(0..100) < LAVA_CHANCE
+                && !cuts_off(&data.map, &pool);
+            let kind = if lava { TileKind::Lava } else { TileKind::Water };
+            for &(x, y) in &pool {
+                data.map[x as usize][y as usize] = Tile::new(kind);
+            }
+        }
+
+        for x in 0..width {
+            for y in 0..height {
+                if data.map[x as usize][y as usize].kind == TileKind::Floor
+                    && !keep_clear(data, (x, y))
+                    && rng.gen_range(0..100) < RUBBLE_CHANCE
+                {
+                    data.map[x as usize][y as usize] = Tile::new(TileKind::Rubble);
+                }
+            }
+        }
+    }
+}
+
+/// whether walling off `tiles` would leave some of the map unreachable from the rest
+fn cuts_off(map: &Map, tiles: &[(i32, i32)]) -> bool {
+    let mut walled = map.clone();
+    for &(x, y) in tiles {
+        walled[x as usize][y as usize] = Tile::wall();
+    }
+    let floor = walled
+        .iter()
+        .enumerate()
+        .flat_map(|(x, column)| {
+            column
+                .iter()
+                .enumerate()
+                .map(move |(y, tile)| ((x as i32, y as i32), tile))
+        })
+        .find(|(_, tile)| tile.passable());
+    floor.is_some_and(|(start, _)| !is_connected(&walled, start))
+}
+
+/// Find the doorways: the tiles where a corridor cuts through the wall of a room.
+pub struct Doorways;
+
+impl MapBuilder for Doorways {
+    fn build(&self, data: &mut BuildData, _rng: &mut GameRng) {
+        let blocked = |map: &Map, x: i32, y: i32| map[x as usize][y as usize].blocked();
+        for room in &data.rooms {
+            // a gap in the top or bottom wall, with wall on either side of it
+            for x in room.x1 + 1..room.x2 {
+                for y in [room.y1, room.y2] {
+                    if !blocked(&data.map, x, y)
+                        && blocked(&data.map, x - 1, y)
+                        && blocked(&data.map, x + 1, y)
+                    {
+                        data.doorways.push((x, y));
+                    }
+                }
+            }
+            // or in the left or right wall
+            for y in room.y1 + 1..room.y2 {
+                for x in [room.x1, room.x2] {
+                    if !blocked(&data.map, x, y)
+                        && blocked(&data.map, x, y - 1)
+                        && blocked(&data.map, x, y + 1)
+                    {
+                        data.doorways.push((x, y));
+                    }
+                }
+            }
+        }
+        // rooms sharing a wall would find the same doorway twice
+        data.doorways.sort();
+        data.doorways.dedup();
+    }
+}
+
+/// Hang closed doors in some of the doorways.
+pub struct Doors;
+
+impl MapBuilder for Doors {
+    fn build(&self, data: &mut BuildData, rng: &mut GameRng) {
+        for &(x, y) in &data.doorways {
+            if rng.gen_range(0..100) < DOOR_CHANCE {
+                data.map[x as usize][y as usize] = Tile::door();
+            }
+        }
+    }
+}
+
+/// Start the player in the middle of the first room and put the exit in the
+/// middle of the last one.
+pub struct FirstAndLastRoom;
+
+impl MapBuilder for FirstAndLastRoom {
+    fn build(&self, data: &mut BuildData, _rng: &mut GameRng) {
+        if let (Some(first), Some(last)) = (data.rooms.first(), data.rooms.last()) {
+            data.start = Some(first.center());
+            data.exit = Some(last.center());
+        }
+    }
+}
+
+/// Start the player on a random bit of floor and put the exit as far from
+/// them as the map allows, for layouts without rooms.
+pub struct FarthestApart;
+
+impl MapBuilder for FarthestApart {
+    fn build(&self, data: &mut BuildData, rng: &mut GameRng) {
+        let width = data.map.len() as i32;
+        let height = data.map[0].len() as i32;
+        let start = loop {
+            let x = rng.gen_range(0..width);
+            let y = rng.gen_range(0..height);
+            if !data.map[x as usize][y as usize].blocked() {
+                break (x, y);
+            }
+        };
+
+        let distances = distances_from(&data.map, start);
+        let mut exit = start;
+        let mut furthest = 0;
+        for (x, column) in distances.iter().enumerate() {
+            for (y, distance) in column.iter().enumerate() {
+                if let Some(distance) = *distance {
+                    if distance > furthest {
+                        furthest = distance;
+                        exit = (x as i32, y as i32);
+                    }
+                }
+            }
+        }
+        data.start = Some(start);
+        data.exit = Some(exit);
+    }
+}
+
+/// Fill the map with monsters and items suited to its depth: room by room,
+/// or area by area if there are no rooms, leaving wherever the player starts
+/// alone.
+pub struct Spawns<'a> {
+    pub monsters: &'a [MonsterTemplate],
+}
+
+impl MapBuilder for Spawns<'_> {
+    fn build(&self, data: &mut BuildData, rng: &mut GameRng) {
+        let areas = if data.rooms.is_empty() {
+            let width = data.map.len() as i32;
+            let height = data.map[0].len() as i32;
+            let mut areas = vec![];
+            for x in (0..width).step_by(SPAWN_AREA_SIZE as usize) {
+                for y in (0..height).step_by(SPAWN_AREA_SIZE as usize) {
+                    let w = SPAWN_AREA_SIZE.min(width - 1 - x);
+                    let h = SPAWN_AREA_SIZE.min(height - 1 - y);
+                    if w >= 2 && h >= 2 {
+                        areas.push(Rect::new(x, y, w, h));
+                    }
+                }
+            }
+            areas
+        } else {
+            data.rooms.clone()
+        };
+
+        for area in areas {
+            if data.start.is_some_and(|start| area.contains(start)) {
+                continue;
+            }
+            place_objects(
+                area,
+                &mut data.spawns,
+                &mut data.map,
+                rng,
+                data.level,
+                self.monsters,
+            );
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use rand::Rng;
+
+use crate::builder::{BuildData, MapBuilder};
+use crate::game::GameRng;
+use crate::map::{create_h_tunnel, create_v_tunnel, distances_from, Map, Tile};
+
+// parameters for the cave generator
+const INITIAL_WALL_CHANCE: u32 = 45; // percent of tiles that start out as wall
+const SMOOTHING_STEPS: u32 = 5;
+// a tile turns to wall when at least this many of the 9 tiles around it
+// (itself included) are walls, and to floor otherwise
+const WALL_NEIGHBOURS: usize = 5;
+// smaller pockets of floor are filled in, bigger ones get a tunnel to the rest
+const MIN_POCKET_SIZE: usize = 20;
+// caves that come out more closed in than this are thrown away and grown again
+const MIN_FLOOR_PERCENT: usize = 35;
+
+/// Grow a cave with a cellular automaton: random noise smoothed out until it
+/// looks like rock, with every pocket of floor either filled in or joined to
+/// the rest, so the whole cave can be walked.
+pub struct Caves;
+
+impl MapBuilder for Caves {
+    fn build(&self, data: &mut BuildData, rng: &mut GameRng) {
+        let width = data.map.len() as i32;
+        let height = data.map[0].len() as i32;
+        loop {
+            let mut map = random_noise(width, height, rng);
+            for _ in 0..SMOOTHING_STEPS {
+                map = smooth(&map);
+            }
+            let floor = connect_pockets(&mut map);
+            if floor * 100 >= (width * height) as usize * MIN_FLOOR_PERCENT {
+                data.map = map;
+                return;
+            }
+        }
+    }
+}
+
+/// walls scattered at random, with a solid wall all around the edge
+fn random_noise(width: i32, height: i32, rng: &mut GameRng) -> Map {
+    let mut map = vec![vec![Tile::wall(); height as usize]; width as usize];
+    for x in 1..width - 1 {
+        for y in 1..height - 1 {
+            if rng.gen_range(0..100) >= INITIAL_WALL_CHANCE {
+                map[x as usize][y as usize] = Tile::empty();
+            }
+        }
+    }
+    map
+}
+
+/// one step of the automaton: each tile becomes whatever most of its neighbours are
+fn smooth(map: &Map) -> Map {
+    let width = map.len();
+    let height = map[0].len();
+    let mut smoothed = vec![vec![Tile::wall(); height]; width];
+    // the edge stays solid, so only the inside is worked out
+    for (x, column) in smoothed.iter_mut().enumerate().take(width - 1).skip(1) {
+        for (y, tile) in column.iter_mut().enumerate().take(height - 1).skip(1) {
+            let walls = (x - 1..=x + 1)
+                .flat_map(|nx| (y - 1..=y + 1).map(move |ny| (nx, ny)))
+                .filter(|&(nx, ny)| map[nx][ny].blocked())
+                .count();
+            if walls < WALL_NEIGHBOURS {
+                *tile = Tile::empty();
+            }
+        }
+    }
+    smoothed
+}
+
+/// Find every separate pocket of floor and keep only the biggest one reachable:
+/// small pockets are filled in with rock, bigger ones are tunnelled to it.
+/// Returns how many floor tiles are left.
+fn connect_pockets(map: &mut Map) -> usize {
+    let mut pockets = find_pockets(map);
+    if pockets.is_empty() {
+        return 0;
+    }
+    pockets.sort_by_key(|pocket| pocket.len());
+    let mut main = pockets.pop().unwrap_or_default();
+
+    for pocket in pockets {
+        if pocket.len() < MIN_POCKET_SIZE {
+            for &(x, y) in &pocket {
+                map[x as usize][y as usize] = Tile::wall();
+            }
+            continue;
+        }
+
+        // dig from the pocket to the closest bit of the main cave
+        let mut closest = (pocket[0], main[0]);
+        let mut closest_distance = i32::MAX;
+        for &from in &pocket {
+            for &to in &main {
+                let distance = (from.0 - to.0).pow(2) + (from.1 - to.1).pow(2);
+                if distance < closest_distance {
+                    closest = (from, to);
+                    closest_distance = distance;
+                }
+            }
+        }
+        let ((x1, y1), (x2, y2)) = closest;
+        create_h_tunnel(x1, x2, y1, map);
+        create_v_tunnel(y1, y2, x2, map);
+        main.extend(pocket);
+    }
+
+    map.iter().flatten().filter(|tile| !tile.blocked()).count()
+}
+
+/// every group of floor tiles that can be walked between
+fn find_pockets(map: &Map) -> Vec<Vec<(i32, i32)>> {
+    let mut seen = vec![vec![false; map[0].len()]; map.len()];
+    let mut pockets = vec![];
+    for x in 0..map.len() {
+        for y in 0..map[0].len() {
+            if map[x][y].blocked() || seen[x][y] {
+                continue;
+            }
+            let mut pocket = vec![];
+            let distances = distances_from(map, (x as i32, y as i32));
+            for (px, column) in distances.iter().enumerate() {
+                for (py, distance) in column.iter().enumerate() {
+                    if distance.is_some() {
+                        seen[px][py] = true;
+                        pocket.push((px as i32, py as i32));
+                    }
+                }
+            }
+            pockets.push(pocket);
+        }
+    }
+    pockets
+}
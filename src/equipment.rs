@@ -0,0 +1,153 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use tcod::colors::*;
+
+use crate::game::{Game, PLAYER};
+use crate::item::UseResult;
+use crate::object::Object;
+
+/// Where a piece of equipment is worn or held. Only one thing fits in each.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Slot {
+    MainHand,
+    OffHand,
+    Head,
+    Body,
+    Ring,
+    Amulet,
+}
+
+impl Slot {
+    /// every slot, in the order the equipment screen lists them
+    pub const ALL: [Slot; 6] = [
+        Slot::MainHand,
+        Slot::OffHand,
+        Slot::Head,
+        Slot::Body,
+        Slot::Ring,
+        Slot::Amulet,
+    ];
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Slot::MainHand => "main hand",
+            Slot::OffHand => "off hand",
+            Slot::Head => "head",
+            Slot::Body => "body",
+            Slot::Ring => "ring",
+            Slot::Amulet => "amulet",
+        };
+        f.write_str(name)
+    }
+}
+
+/// An object that can be equipped, adding its bonuses to the wearer's stats
+/// for as long as it's on.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Equipment {
+    pub slot: Slot,
+    pub equipped: bool,
+    pub power_bonus: i32,
+    pub defense_bonus: i32,
+    pub magic_bonus: i32,
+    pub magic_defense_bonus: i32,
+    pub max_hp_bonus: i32,
+    /// how far it shoots, for ranged weapons (0 for everything else)
+    pub range: i32,
+}
+
+impl Equipment {
+    /// a piece for `slot` with no bonuses yet, not equipped
+    pub fn new(slot: Slot) -> Self {
+        Equipment {
+            slot,
+            equipped: false,
+            power_bonus: 0,
+            defense_bonus: 0,
+            magic_bonus: 0,
+            magic_defense_bonus: 0,
+            max_hp_bonus: 0,
+            range: 0,
+        }
+    }
+
+    /// its bonuses, like "+3 power, +1 defense"
+    pub fn describe(&self) -> String {
+        let bonuses = [
+            (self.power_bonus, "power"),
+            (self.defense_bonus, "defense"),
+            (self.magic_bonus, "magic"),
+            (self.magic_defense_bonus, "magic defense"),
+            (self.max_hp_bonus, "max HP"),
+        ];
+        let mut description: Vec<_> = bonuses
+            .iter()
+            .filter(|(bonus, _)| *bonus != 0)
+            .map(|(bonus, stat)| format!("{:+} {}", bonus, stat))
+            .collect();
+        if self.range > 0 {
+            description.push(format!("range {}", self.range));
+        }
+        description.join(", ")
+    }
+}
+
+/// the inventory index of whatever is equipped in `slot`, if anything
+pub fn equipped_in_slot(slot: Slot, inventory: &[Object]) -> Option<usize> {
+    inventory.iter().position(|item| {
+        item.equipment
+            .is_some_and(|equipment| equipment.equipped && equipment.slot == slot)
+    })
+}
+
+/// Put on the equipment at `inventory_id`, or take it off if it's on already.
+/// Whatever was in its slot before comes off to make room.
+pub fn toggle_equipment(inventory_id: usize, game: &mut Game, objects: &mut [Object]) -> UseResult {
+    let equipment = match game.inventory[inventory_id].equipment {
+        Some(equipment) => equipment,
+        None => return UseResult::Cancelled,
+    };
+    if equipment.equipped {
+        dequip(inventory_id, game);
+    } else {
+        if let Some(current) = equipped_in_slot(equipment.slot, &game.inventory) {
+            dequip(current, game);
+        }
+        equip(inventory_id, game);
+    }
+    clamp_hp(&mut objects[PLAYER], game);
+    UseResult::UsedAndKept
+}
+
+fn equip(inventory_id: usize, game: &mut Game) {
+    let item = &mut game.inventory[inventory_id];
+    if let Some(equipment) = item.equipment.as_mut() {
+        equipment.equipped = true;
+        let text = format!("Equipped {} on {}.", item.name, equipment.slot);
+        game.messages.add(text, LIGHT_GREEN);
+    }
+}
+
+/// Take off the equipment at `inventory_id`. The player's HP may be over their
+/// maximum afterwards, see `clamp_hp`.
+pub fn dequip(inventory_id: usize, game: &mut Game) {
+    let item = &mut game.inventory[inventory_id];
+    if let Some(equipment) = item.equipment.as_mut() {
+        if equipment.equipped {
+            equipment.equipped = false;
+            let text = format!("Dequipped {} from {}.", item.name, equipment.slot);
+            game.messages.add(text, LIGHT_YELLOW);
+        }
+    }
+}
+
+/// after taking something off, lose the HP it was giving
+pub fn clamp_hp(object: &mut Object, game: &Game) {
+    let max_hp = object.max_hp(game);
+    if let Some(fighter) = object.fighter.as_mut() {
+        fighter.hp = fighter.hp.min(max_hp);
+    }
+}
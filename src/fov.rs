@@ -0,0 +1,116 @@
+/// Field of view over a grid of transparent/opaque cells.
+///
+/// This replaces the tcod FOV map so the game rules can run without SDL.
+/// It's a simple ray caster, roughly what tcod calls `FovAlgorithm::Basic`.
+#[derive(Clone, Debug, Default)]
+pub struct Fov {
+    width: i32,
+    height: i32,
+    transparent: Vec<bool>,
+    visible: Vec<bool>,
+}
+
+impl Fov {
+    /// create a new FOV map where every cell is opaque
+    pub fn new(width: i32, height: i32) -> Self {
+        let size = (width * height) as usize;
+        Fov {
+            width,
+            height,
+            transparent: vec![false; size],
+            visible: vec![false; size],
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            None
+        } else {
+            Some((x + y * self.width) as usize)
+        }
+    }
+
+    /// set whether light (and sight) can pass through the given cell
+    pub fn set(&mut self, x: i32, y: i32, transparent: bool) {
+        if let Some(i) = self.index(x, y) {
+            self.transparent[i] = transparent;
+        }
+    }
+
+    pub fn is_transparent(&self, x: i32, y: i32) -> bool {
+        self.index(x, y).is_some_and(|i| self.transparent[i])
+    }
+
+    /// return true if the cell was visible the last time the FOV was computed
+    pub fn is_in_fov(&self, x: i32, y: i32) -> bool {
+        self.index(x, y).is_some_and(|i| self.visible[i])
+    }
+
+    /// recompute what can be seen from (x, y) within the given radius
+    pub fn compute(&mut self, x: i32, y: i32, radius: i32, light_walls: bool) {
+        self.visible.iter_mut().for_each(|v| *v = false);
+        let origin = match self.index(x, y) {
+            Some(i) => i,
+            None => return,
+        };
+        self.visible[origin] = true;
+
+        // cast a ray towards every cell on the edge of the square around the origin
+        let mut edge = vec![];
+        for i in -radius..=radius {
+            edge.push((x + i, y - radius));
+            edge.push((x + i, y + radius));
+            edge.push((x - radius, y + i));
+            edge.push((x + radius, y + i));
+        }
+        for (tx, ty) in edge {
+            self.cast_ray(x, y, tx, ty, radius, light_walls);
+        }
+    }
+
+    fn cast_ray(&mut self, x: i32, y: i32, tx: i32, ty: i32, radius: i32, light_walls: bool) {
+        for (cx, cy) in line((x, y), (tx, ty)) {
+            let i = match self.index(cx, cy) {
+                Some(i) => i,
+                None => return,
+            };
+            let (dx, dy) = (cx - x, cy - y);
+            if dx * dx + dy * dy > radius * radius {
+                return;
+            }
+            if self.transparent[i] {
+                self.visible[i] = true;
+            } else {
+                // the ray stops here, but the wall itself may be lit
+                if light_walls {
+                    self.visible[i] = true;
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Bresenham line from `from` to `to`, not including the starting cell.
+pub fn line(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let sx = if x < to.0 { 1 } else { -1 };
+    let sy = if y < to.1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let mut points = vec![];
+    while (x, y) != to {
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+        points.push((x, y));
+    }
+    points
+}
@@ -0,0 +1,493 @@
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
+use tcod::colors::*;
+
+use crate::fov::Fov;
+use crate::item::{drop_item, pick_item_up, use_item, UseResult};
+use crate::map::{make_map, Map, TileKind};
+use crate::messages::Messages;
+use crate::monsters::MonsterTemplate;
+use crate::prefab::Prefab;
+use crate::ranged::{player_fire, Shot};
+use crate::replay::Recorder;
+use crate::object::{
+    ai_take_turn, player_move_or_attack, DamageKind, DeathCallback, Fighter, Object,
+};
+use crate::schedule::{
+    run_until_ready, Action, Actors, ACTION_ENERGY, NORMAL_SPEED, TICKS_PER_TURN,
+};
+use crate::status::{lose_stunned_turn, tick_effects, StatusKind};
+use crate::void::tick_void;
+
+// player will always be the first object
+pub const PLAYER: usize = 0;
+
+// size of the map
+pub const MAP_WIDTH: i32 = 80;
+pub const MAP_HEIGHT: i32 = 43;
+
+// experience and level-ups
+pub const LEVEL_UP_BASE: i32 = 100;
+pub const LEVEL_UP_FACTOR: i32 = 75;
+
+pub const FOV_LIGHT_WALLS: bool = true; // light walls or not
+pub const FOV_RADIUS: i32 = 8;
+
+/// The one random number generator of a run. Everything random (map generation,
+/// spawning, AI) must draw from it, so the same seed always gives the same game.
+pub type GameRng = Pcg64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlayerAction {
+    TookTurn,
+    DidntTakeTurn,
+    Exit,
+}
+
+/// Something the player wants to do this turn, independent of how it was input.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+    /// move (or attack whatever is in the way) by the given amount
+    Move(i32, i32),
+    /// do nothing and let the monsters act
+    Wait,
+    /// pick up the item the player is standing on
+    PickUp,
+    /// use the item at this inventory index, aimed at a tile if the item needs one
+    UseItem(usize, Option<(i32, i32)>),
+    /// drop the item at this inventory index
+    Drop(usize),
+    /// go down the stairs the player is standing on
+    Descend,
+    /// close the door next to the player, in the given direction
+    CloseDoor(i32, i32),
+    /// shoot the equipped ranged weapon at a tile
+    Fire(i32, i32),
+    /// spend a pending level-up on the given stat
+    LevelUp(Stat),
+}
+
+/// What a level-up can improve.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Stat {
+    MaxHp,
+    Power,
+    Defense,
+}
+
+/// how much XP is needed to go from `level` to the next one
+pub fn level_up_xp(level: i32) -> i32 {
+    LEVEL_UP_BASE + level * LEVEL_UP_FACTOR
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Game {
+    pub map: Map,
+    pub messages: Messages,
+    pub inventory: Vec<Object>,
+    /// how deep the player is, starting at 1
+    pub dungeon_level: u32,
+    /// where the stairs down are on this floor, if it has any
+    pub stairs: Option<(i32, i32)>,
+    /// rebuilt from the map after loading, so it's never saved
+    #[serde(skip)]
+    pub fov: Fov,
+    /// the seed the run was started with
+    pub seed: u64,
+    pub rng: GameRng,
+    /// how many scheduler ticks have passed since the start of the run
+    pub time: u64,
+    /// how many commands the run has been given, to match it up with its replay
+    pub commands: u64,
+    /// what was shot during the last step, so it can be animated; never saved
+    #[serde(skip)]
+    pub shots: Vec<Shot>,
+}
+
+impl Game {
+    pub fn find_object(x: i32, y: i32, objects: &[Object]) -> Option<usize> {
+        // iterate through the objects array and return the first index that matches
+        objects.iter().position(|object| object.pos() == (x, y))
+    }
+
+    /// open or close the door at (x, y), and let the FOV know whether it can
+    /// be seen through now
+    pub fn set_door(&mut self, x: i32, y: i32, open: bool) {
+        self.map[x as usize][y as usize].kind = if open {
+            TileKind::OpenDoor
+        } else {
+            TileKind::Door
+        };
+        self.fov.set(x, y, open);
+    }
+}
+
+/// The whole state of a run: the map, every object, the FOV and the message log.
+/// Frontends feed it `Command`s and draw whatever is in here.
+pub struct World {
+    pub game: Game,
+    pub objects: Vec<Object>,
+    /// what can be spawned on new levels; loaded from data, so it's never saved
+    pub monsters: Vec<MonsterTemplate>,
+    /// the hand-made pieces new levels may get; loaded from data too
+    pub prefabs: Vec<Prefab>,
+    /// where the commands given to `step` are written, if they're being recorded
+    pub recorder: Option<Recorder>,
+}
+
+impl World {
+    /// create a fresh dungeon with the player in the first room, generated from `seed`
+    pub fn new(seed: u64, monsters: Vec<MonsterTemplate>, prefabs: Vec<Prefab>) -> Self {
+        // create object representing the player
+        let mut player = Object::new(25, 23, '@', "player", YELLOW, true);
+        player.alive = true;
+        player.fighter = Some(Fighter {
+            max_hp: 30,
+            hp: 30,
+            defense: 2,
+            power: 5,
+            magic_defense: 0,
+            magic: 0,
+            attack: DamageKind::Physical,
+            on_hit: None,
+            speed: NORMAL_SPEED,
+            xp: 0,
+            on_death: DeathCallback::Player,
+        });
+        // the player gets to move first
+        player.energy = ACTION_ENERGY;
+
+        // the list of objects
+        let mut objects = vec![player];
+        let mut rng = GameRng::seed_from_u64(seed);
+        let (map, stairs) = make_map(
+            MAP_WIDTH,
+            MAP_HEIGHT,
+            &mut objects,
+            &mut rng,
+            1,
+            &monsters,
+            &prefabs,
+        );
+
+        let mut world = World::from_parts(map, objects, seed, rng, monsters, prefabs);
+        world.game.stairs = stairs;
+        world
+    }
+
+    /// build a world around an existing map and objects (the player must be `objects[PLAYER]`)
+    pub fn from_parts(
+        map: Map,
+        objects: Vec<Object>,
+        seed: u64,
+        rng: GameRng,
+        monsters: Vec<MonsterTemplate>,
+        prefabs: Vec<Prefab>,
+    ) -> Self {
+        let game = Game {
+            map,
+            messages: Messages::new(),
+            inventory: vec![],
+            dungeon_level: 1,
+            stairs: None,
+            fov: Fov::default(),
+            seed,
+            rng,
+            time: 0,
+            commands: 0,
+            shots: vec![],
+        };
+        World::from_game(game, objects, monsters, prefabs)
+    }
+
+    /// build a world around a game whose FOV may be missing (e.g. just loaded from a save)
+    pub fn from_game(
+        game: Game,
+        objects: Vec<Object>,
+        monsters: Vec<MonsterTemplate>,
+        prefabs: Vec<Prefab>,
+    ) -> Self {
+        let mut world = World {
+            game,
+            objects,
+            monsters,
+            prefabs,
+            recorder: None,
+        };
+        world.sync_fov();
+        world.update_fov();
+        world
+    }
+
+    /// copy the sight-blocking tiles of the map into a fresh FOV map
+    pub fn sync_fov(&mut self) {
+        let map = &self.game.map;
+        self.game.fov = Fov::new(map.len() as i32, map[0].len() as i32);
+        for (x, column) in map.iter().enumerate() {
+            for (y, tile) in column.iter().enumerate() {
+                self.game.fov.set(x as i32, y as i32, !tile.block_sight());
+            }
+        }
+    }
+
+    /// recompute what the player can see and mark it as explored
+    pub fn update_fov(&mut self) {
+        let (px, py) = self.objects[PLAYER].pos();
+        let fov = &mut self.game.fov;
+        fov.compute(px, py, FOV_RADIUS, FOV_LIGHT_WALLS);
+
+        for (x, column) in self.game.map.iter_mut().enumerate() {
+            for (y, tile) in column.iter_mut().enumerate() {
+                if fov.is_in_fov(x as i32, y as i32) {
+                    tile.explored = true;
+                }
+            }
+        }
+    }
+
+    /// whether the player has enough XP to level up, and must choose a stat
+    pub fn can_level_up(&self) -> bool {
+        let player = &self.objects[PLAYER];
+        player
+            .fighter
+            .is_some_and(|f| f.xp >= level_up_xp(player.level))
+    }
+
+    /// level the player up, improving the chosen stat, if they have the XP for it
+    pub fn level_up(&mut self, stat: Stat) {
+        if !self.can_level_up() {
+            return;
+        }
+        let player = &mut self.objects[PLAYER];
+        let xp_needed = level_up_xp(player.level);
+        player.level += 1;
+        self.game.messages.add(
+            format!(
+                "Your battle skills grow stronger! You reached level {}!",
+                player.level
+            ),
+            YELLOW,
+        );
+        let fighter = player.fighter.as_mut().unwrap();
+        fighter.xp -= xp_needed;
+        match stat {
+            Stat::MaxHp => {
+                fighter.max_hp += 10;
+                fighter.hp += 10;
+            }
+            Stat::Power => {
+                fighter.power += 1;
+            }
+            Stat::Defense => {
+                fighter.defense += 1;
+            }
+        }
+    }
+
+    /// advance to the next level, keeping the player (and their HP) but
+    /// nothing else from the old floor
+    pub fn next_level(&mut self) {
+        self.game.messages.add(
+            "You descend deeper into the heart of the dungeon...",
+            RED,
+        );
+        self.game.dungeon_level += 1;
+        (self.game.map, self.game.stairs) = make_map(
+            MAP_WIDTH,
+            MAP_HEIGHT,
+            &mut self.objects,
+            &mut self.game.rng,
+            self.game.dungeon_level,
+            &self.monsters,
+            &self.prefabs,
+        );
+        self.sync_fov();
+        self.update_fov();
+    }
+
+    /// advance the game by one player command, letting everything else act
+    /// until the player is ready for the next one
+    pub fn step(&mut self, command: Command) -> PlayerAction {
+        use PlayerAction::*;
+        if !self.objects[PLAYER].alive {
+            return DidntTakeTurn;
+        }
+        self.record(command);
+        self.game.shots.clear();
+        if let Command::LevelUp(stat) = command {
+            self.level_up(stat);
+            // choosing doesn't cost a turn
+            return DidntTakeTurn;
+        }
+
+        let dungeon_level = self.game.dungeon_level;
+        let action = if self.objects[PLAYER].has_effect(StatusKind::Stun) {
+            // whatever the player wanted to do, the turn is lost
+            self.game
+                .messages
+                .add("You are stunned and can't act!", StatusKind::Stun.color());
+            lose_stunned_turn(PLAYER, &mut self.game, &mut self.objects);
+            Action::Wait
+        } else {
+            match self.player_act(command) {
+                Some(action) => action,
+                None => return DidntTakeTurn,
+            }
+        };
+        if self.game.dungeon_level != dungeon_level {
+            // the new floor starts fresh, nothing gets to act on arrival
+            return TookTurn;
+        }
+        self.update_fov();
+
+        // let monsters (and time) go on until the player can act again
+        self.objects[PLAYER].energy -= action.cost();
+        run_until_ready(self, PLAYER);
+        // monsters may have opened doors, changing what can be seen
+        self.update_fov();
+        TookTurn
+    }
+
+    /// keep the command in the replay, if one is being recorded
+    fn record(&mut self, command: Command) {
+        self.game.commands += 1;
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = recorder.record(command) {
+                // better to keep playing without a replay than to stop the game
+                self.game
+                    .messages
+                    .add(format!("Could not record the replay: {}", e), RED);
+                self.recorder = None;
+            }
+        }
+    }
+
+    /// carry out the player's part of a turn, returning what it cost them
+    /// (or None if nothing was done)
+    fn player_act(&mut self, command: Command) -> Option<Action> {
+        match command {
+            Command::Move(dx, dy) => {
+                let (dx, dy) = if self.objects[PLAYER].has_effect(StatusKind::Confusion) {
+                    // the player staggers off in a random direction
+                    (self.game.rng.gen_range(-1..=1), self.game.rng.gen_range(-1..=1))
+                } else {
+                    (dx, dy)
+                };
+                if (dx, dy) == (0, 0) {
+                    // staggering on the spot is as good as waiting
+                    return Some(Action::Wait);
+                }
+                Some(player_move_or_attack(dx, dy, &mut self.game, &mut self.objects))
+            }
+            Command::Wait => Some(Action::Wait),
+            Command::PickUp => {
+                // pick up an item
+                let player_pos = self.objects[PLAYER].pos();
+                let item_id = self
+                    .objects
+                    .iter()
+                    .position(|object| object.pos() == player_pos && object.item.is_some());
+                match item_id {
+                    Some(item_id) => {
+                        pick_item_up(item_id, &mut self.game, &mut self.objects);
+                        Some(Action::PickUp)
+                    }
+                    None => None,
+                }
+            }
+            Command::UseItem(inventory_id, target) => {
+                let item = self.game.inventory.get(inventory_id)?.item;
+                match use_item(inventory_id, target, &mut self.game, &mut self.objects) {
+                    UseResult::UsedUp | UseResult::UsedAndKept => {
+                        Some(item.map_or(Action::Read, |item| item.action()))
+                    }
+                    UseResult::Cancelled => {
+                        self.game.messages.add("Cancelled", WHITE);
+                        None
+                    }
+                }
+            }
+            Command::Descend => {
+                // go down stairs, if the player is on them
+                if self.game.stairs != Some(self.objects[PLAYER].pos()) {
+                    return None;
+                }
+                self.next_level();
+                Some(Action::Move)
+            }
+            // handled before the player acts, since it never costs a turn
+            Command::LevelUp(_) => None,
+            Command::Drop(inventory_id) => {
+                if inventory_id >= self.game.inventory.len() {
+                    return None;
+                }
+                drop_item(inventory_id, &mut self.game, &mut self.objects);
+                Some(Action::Drop)
+            }
+            Command::Fire(x, y) => {
+                player_fire((x, y), &mut self.game, &mut self.objects).then_some(Action::Attack)
+            }
+            Command::CloseDoor(dx, dy) => {
+                // only doors right next to the player can be reached
+                if !(-1..=1).contains(&dx) || !(-1..=1).contains(&dy) {
+                    return None;
+                }
+                let (x, y) = self.objects[PLAYER].pos();
+                let (x, y) = (x + dx, y + dy);
+                let tile = self.game.map.get(x as usize).and_then(|column| column.get(y as usize));
+                if !tile.is_some_and(|tile| tile.is_open_door()) {
+                    self.game.messages.add("There is no open door there.", WHITE);
+                    return None;
+                }
+                if Game::find_object(x, y, &self.objects).is_some() {
+                    self.game.messages.add("Something is in the way.", WHITE);
+                    return None;
+                }
+                self.game.set_door(x, y, false);
+                self.game.messages.add("You close the door.", WHITE);
+                Some(Action::Door)
+            }
+        }
+    }
+}
+
+impl Actors for World {
+    fn count(&self) -> usize {
+        self.objects.len()
+    }
+
+    fn speed(&self, id: usize) -> Option<i32> {
+        // the player and living monsters act, nothing else does
+        let object = &self.objects[id];
+        if object.alive && (id == PLAYER || object.ai.is_some()) {
+            object.fighter.map(|f| f.speed)
+        } else {
+            None
+        }
+    }
+
+    fn energy(&mut self, id: usize) -> &mut i32 {
+        &mut self.objects[id].energy
+    }
+
+    fn act(&mut self, id: usize) -> Action {
+        ai_take_turn(id, &mut self.game, &mut self.objects)
+    }
+
+    fn time(&mut self) -> &mut u64 {
+        &mut self.game.time
+    }
+
+    fn end_turn(&mut self) {
+        // whatever is said from now on happens on the next turn
+        let turn = self.game.time / TICKS_PER_TURN as u64;
+        self.game.messages.set_turn(turn);
+        // everything's effects run out a little
+        for id in 0..self.objects.len() {
+            tick_effects(id, &mut self.game, &mut self.objects);
+        }
+        // and the void creeps on
+        tick_void(&mut self.game, &mut self.objects);
+    }
+}
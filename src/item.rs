@@ -0,0 +1,442 @@
+use serde::{Deserialize, Serialize};
+use tcod::colors::*;
+
+use crate::equipment::{clamp_hp, dequip, toggle_equipment, Equipment, Slot};
+use crate::game::{Game, PLAYER};
+use crate::ranged::{throw_knife, THROW_RANGE};
+use crate::object::{spell_damage, DamageKind, Object};
+use crate::schedule::Action;
+use crate::status::{StatusEffect, StatusKind};
+use crate::void::{cleanse, is_void_rift};
+
+// the inventory menu is lettered a-z, so it can't hold more than that
+pub const INVENTORY_SIZE: usize = 26;
+
+const HEAL_AMOUNT: i32 = 10;
+const LIGHTNING_DAMAGE: i32 = 20;
+const LIGHTNING_RANGE: i32 = 5;
+const CONFUSE_RANGE: i32 = 8;
+const CONFUSE_NUM_TURNS: i32 = 10;
+const FIREBALL_RADIUS: i32 = 3;
+const FIREBALL_DAMAGE: i32 = 12;
+const FIREBALL_RANGE: i32 = 8;
+const REGENERATION_NUM_TURNS: i32 = 15;
+const REGENERATION_AMOUNT: i32 = 1;
+const PURIFY_RADIUS: i32 = 6;
+const PURIFY_DAMAGE: i32 = 15;
+const BOW_RANGE: i32 = 8;
+// how many arrows or knives are found together
+const ARROWS_IN_BUNDLE: u32 = 12;
+const KNIVES_IN_BUNDLE: u32 = 5;
+
+/// Something that can be picked up and used.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Item {
+    Heal,
+    Lightning,
+    Confuse,
+    Fireball,
+    Regeneration,
+    Purify,
+    Sword,
+    Shield,
+    Helmet,
+    LeatherArmor,
+    RingOfSorcery,
+    AmuletOfWarding,
+    Bow,
+    Arrows,
+    ThrowingKnives,
+}
+
+impl Item {
+    /// how far away the tile picked for this item may be, if it needs one at all
+    pub fn target_range(self) -> Option<f32> {
+        match self {
+            Item::Confuse => Some(CONFUSE_RANGE as f32),
+            Item::Fireball => Some(FIREBALL_RANGE as f32),
+            Item::ThrowingKnives => Some(THROW_RANGE as f32),
+            _ => None,
+        }
+    }
+
+    /// how far around its target it reaches, 0 if it only hits the target itself
+    pub fn area_radius(self) -> i32 {
+        match self {
+            Item::Fireball => FIREBALL_RADIUS,
+            _ => 0,
+        }
+    }
+
+    /// using a potion is quicker than reading a scroll
+    pub fn action(self) -> Action {
+        match self {
+            Item::Heal | Item::Regeneration => Action::Quaff,
+            Item::Lightning | Item::Confuse | Item::Fireball | Item::Purify => Action::Read,
+            Item::Arrows | Item::ThrowingKnives => Action::Attack,
+            _ => Action::Equip,
+        }
+    }
+
+    /// what it gives when worn, if it's something that can be
+    pub fn equipment(self) -> Option<Equipment> {
+        let equipment = match self {
+            Item::Sword => Equipment {
+                power_bonus: 3,
+                ..Equipment::new(Slot::MainHand)
+            },
+            Item::Shield => Equipment {
+                defense_bonus: 1,
+                ..Equipment::new(Slot::OffHand)
+            },
+            Item::Helmet => Equipment {
+                defense_bonus: 1,
+                magic_defense_bonus: 1,
+                ..Equipment::new(Slot::Head)
+            },
+            Item::LeatherArmor => Equipment {
+                defense_bonus: 2,
+                ..Equipment::new(Slot::Body)
+            },
+            Item::RingOfSorcery => Equipment {
+                magic_bonus: 3,
+                ..Equipment::new(Slot::Ring)
+            },
+            Item::AmuletOfWarding => Equipment {
+                magic_defense_bonus: 4,
+                max_hp_bonus: 10,
+                ..Equipment::new(Slot::Amulet)
+            },
+            Item::Bow => Equipment {
+                range: BOW_RANGE,
+                ..Equipment::new(Slot::MainHand)
+            },
+            _ => return None,
+        };
+        Some(equipment)
+    }
+
+    /// whether several of it share one inventory slot
+    pub fn stacks(self) -> bool {
+        matches!(self, Item::Arrows | Item::ThrowingKnives)
+    }
+
+    /// create this item lying on the floor at (x, y)
+    pub fn spawn(self, x: i32, y: i32) -> Object {
+        let mut item = match self {
+            Item::Heal => {
+                // create a healing potion
+                Object::new(x, y, '!', "healing potion", VIOLET, false)
+            }
+            Item::Lightning => {
+                // create a lightning bolt scroll
+                Object::new(x, y, '?', "scroll of lightning bolt", LIGHT_YELLOW, false)
+            }
+            Item::Fireball => {
+                // create a fireball scroll
+                Object::new(x, y, '?', "scroll of fireball", LIGHT_ORANGE, false)
+            }
+            Item::Confuse => {
+                // create a confuse scroll
+                Object::new(x, y, '?', "scroll of confusion", LIGHT_CYAN, false)
+            }
+            Item::Regeneration => {
+                // create a regeneration potion
+                Object::new(x, y, '!', "potion of regeneration", LIGHT_PINK, false)
+            }
+            Item::Purify => {
+                // create a purification scroll
+                Object::new(x, y, '?', "scroll of purification", WHITE, false)
+            }
+            Item::Sword => Object::new(x, y, '/', "sword", SKY, false),
+            Item::Shield => Object::new(x, y, '[', "shield", DARKER_ORANGE, false),
+            Item::Helmet => Object::new(x, y, '[', "iron helmet", LIGHT_GREY, false),
+            Item::LeatherArmor => Object::new(x, y, '[', "leather armor", SEPIA, false),
+            Item::RingOfSorcery => Object::new(x, y, '=', "ring of sorcery", LIGHT_MAGENTA, false),
+            Item::AmuletOfWarding => Object::new(x, y, '"', "amulet of warding", LIGHT_BLUE, false),
+            Item::Bow => Object::new(x, y, ')', "bow", LIGHT_SEPIA, false),
+            Item::Arrows => {
+                let mut arrows = Object::new(x, y, '(', "arrows", LIGHT_SEPIA, false);
+                arrows.count = ARROWS_IN_BUNDLE;
+                arrows
+            }
+            Item::ThrowingKnives => {
+                let mut knives = Object::new(x, y, '(', "throwing knives", LIGHT_GREY, false);
+                knives.count = KNIVES_IN_BUNDLE;
+                knives
+            }
+        };
+        item.item = Some(self);
+        item.equipment = self.equipment();
+        item
+    }
+}
+
+pub enum UseResult {
+    UsedUp,
+    /// it did something, but it's still there (equipment)
+    UsedAndKept,
+    Cancelled,
+}
+
+/// add to the player's inventory and remove from the map
+pub fn pick_item_up(object_id: usize, game: &mut Game, objects: &mut Vec<Object>) {
+    // things that stack go onto the pile the player already has, if any
+    let stack = objects[object_id]
+        .item
+        .filter(|item| item.stacks())
+        .and_then(|item| game.inventory.iter().position(|other| other.item == Some(item)));
+    if let Some(stack) = stack {
+        let item = objects.swap_remove(object_id);
+        game.messages
+            .add(format!("You picked up {} {}!", item.count, item.name), GREEN);
+        game.inventory[stack].count += item.count;
+    } else if game.inventory.len() >= INVENTORY_SIZE {
+        game.messages.add(
+            format!(
+                "Your inventory is full, cannot pick up {}.",
+                objects[object_id].name
+            ),
+            RED,
+        );
+    } else {
+        let item = objects.swap_remove(object_id);
+        game.messages
+            .add(format!("You picked up a {}!", item.name), GREEN);
+        game.inventory.push(item);
+    }
+}
+
+/// remove from the player's inventory and put it on the floor under the player
+pub fn drop_item(inventory_id: usize, game: &mut Game, objects: &mut Vec<Object>) {
+    // take it off first, if it's being worn
+    dequip(inventory_id, game);
+    clamp_hp(&mut objects[PLAYER], game);
+    let mut item = game.inventory.remove(inventory_id);
+    item.set_pos(objects[PLAYER].x, objects[PLAYER].y);
+    game.messages
+        .add(format!("You dropped a {}.", item.name), YELLOW);
+    objects.push(item);
+}
+
+/// use an item from the inventory, aimed at `target` if the item needs a tile
+pub fn use_item(
+    inventory_id: usize,
+    target: Option<(i32, i32)>,
+    game: &mut Game,
+    objects: &mut Vec<Object>,
+) -> UseResult {
+    use Item::*;
+    // just call the "use_function" if it is defined
+    if let Some(item) = game.inventory[inventory_id].item {
+        let on_use = match item {
+            Heal => cast_heal,
+            Lightning => cast_lightning,
+            Confuse => cast_confuse,
+            Fireball => cast_fireball,
+            Regeneration => cast_regeneration,
+            Purify => cast_purify,
+            Sword | Shield | Helmet | LeatherArmor | RingOfSorcery | AmuletOfWarding | Bow => {
+                return toggle_equipment(inventory_id, game, objects);
+            }
+            ThrowingKnives => return throw_knife(inventory_id, target, game, objects),
+            Arrows => {
+                game.messages
+                    .add("Arrows are shot with a bow: equip one, then press F.", WHITE);
+                return UseResult::Cancelled;
+            }
+        };
+        let result = on_use(target, game, objects);
+        if let UseResult::UsedUp = result {
+            // destroy after use, unless it was cancelled for some reason
+            game.inventory.remove(inventory_id);
+        }
+        result
+    } else {
+        game.messages.add(
+            format!("The {} cannot be used.", game.inventory[inventory_id].name),
+            WHITE,
+        );
+        UseResult::Cancelled
+    }
+}
+
+fn cast_heal(_target: Option<(i32, i32)>, game: &mut Game, objects: &mut [Object]) -> UseResult {
+    // heal the player
+    if let Some(fighter) = objects[PLAYER].fighter {
+        if fighter.hp == objects[PLAYER].max_hp(game) {
+            game.messages.add("You are already at full health.", RED);
+            return UseResult::Cancelled;
+        }
+        game.messages
+            .add("Your wounds start to feel better!", LIGHT_VIOLET);
+        objects[PLAYER].heal(HEAL_AMOUNT, game);
+        return UseResult::UsedUp;
+    }
+    UseResult::Cancelled
+}
+
+fn cast_regeneration(_target: Option<(i32, i32)>, game: &mut Game, objects: &mut [Object]) -> UseResult {
+    // the player slowly heals over the next turns
+    game.messages
+        .add("You feel your flesh slowly knitting itself back together.", LIGHT_PINK);
+    objects[PLAYER].add_effect(StatusEffect {
+        kind: StatusKind::Regeneration,
+        turns: REGENERATION_NUM_TURNS,
+        potency: REGENERATION_AMOUNT,
+    });
+    UseResult::UsedUp
+}
+
+fn cast_purify(_target: Option<(i32, i32)>, game: &mut Game, objects: &mut [Object]) -> UseResult {
+    // drive the void back from the ground around the player, and hurt any
+    // rift close enough
+    let (x, y) = objects[PLAYER].pos();
+    let rifts: Vec<usize> = (0..objects.len())
+        .filter(|&id| is_void_rift(&objects[id]) && objects[id].distance(x, y) <= PURIFY_RADIUS as f32)
+        .collect();
+    let cleansed = cleanse(game, (x, y), PURIFY_RADIUS);
+    if cleansed == 0 && rifts.is_empty() {
+        game.messages.add("There is no void here to purify.", RED);
+        return UseResult::Cancelled;
+    }
+
+    game.messages
+        .add("A wave of pure light washes the void away!", WHITE);
+    for id in rifts {
+        game.messages.add(
+            format!("The {} shudders for {} hit points.", objects[id].name, PURIFY_DAMAGE),
+            WHITE,
+        );
+        if let Some(xp) = objects[id].take_damage(PURIFY_DAMAGE, game) {
+            objects[PLAYER].gain_xp(xp);
+        }
+    }
+    UseResult::UsedUp
+}
+
+fn cast_lightning(_target: Option<(i32, i32)>, game: &mut Game, objects: &mut [Object]) -> UseResult {
+    // find closest enemy (inside a maximum range) and damage it
+    let monster_id = closest_monster(LIGHTNING_RANGE, game, objects);
+    if let Some(monster_id) = monster_id {
+        // zap it!
+        let damage = spell_damage(LIGHTNING_DAMAGE, &objects[PLAYER], &objects[monster_id], game).max(0);
+        game.messages.add(
+            format!(
+                "A lightning bolt strikes the {} with a loud thunder! \
+                 The damage is {} magic hit points.",
+                objects[monster_id].name, damage
+            ),
+            LIGHT_BLUE,
+        );
+        if let Some(xp) = objects[monster_id].take_damage(damage, game) {
+            objects[PLAYER].gain_xp(xp);
+        }
+        UseResult::UsedUp
+    } else {
+        // no enemy found within maximum range
+        game.messages
+            .add("No enemy is close enough to strike.", RED);
+        UseResult::Cancelled
+    }
+}
+
+fn cast_confuse(target: Option<(i32, i32)>, game: &mut Game, objects: &mut [Object]) -> UseResult {
+    // the monster standing on the chosen tile gets confused
+    let monster_id = target.and_then(|(x, y)| target_monster(x, y, CONFUSE_RANGE, game, objects));
+    if let Some(monster_id) = monster_id {
+        // confused monsters stumble around instead of following their AI,
+        // until the effect wears off
+        objects[monster_id].add_effect(StatusEffect {
+            kind: StatusKind::Confusion,
+            turns: CONFUSE_NUM_TURNS,
+            potency: 0,
+        });
+        game.messages.add(
+            format!(
+                "The eyes of {} look vacant, as it starts to stumble around!",
+                objects[monster_id].name
+            ),
+            LIGHT_GREEN,
+        );
+        UseResult::UsedUp
+    } else {
+        game.messages.add("There is no enemy there to confuse.", RED);
+        UseResult::Cancelled
+    }
+}
+
+fn cast_fireball(target: Option<(i32, i32)>, game: &mut Game, objects: &mut [Object]) -> UseResult {
+    let (x, y) = match target {
+        Some(tile) if objects[PLAYER].distance(tile.0, tile.1) <= FIREBALL_RANGE as f32 => tile,
+        _ => {
+            game.messages.add("That is out of reach.", RED);
+            return UseResult::Cancelled;
+        }
+    };
+    game.messages.add(
+        format!(
+            "The fireball explodes, burning everything within {} tiles!",
+            FIREBALL_RADIUS
+        ),
+        ORANGE,
+    );
+
+    let caster = objects[PLAYER].clone();
+    let mut xp_to_gain = 0;
+    for (id, obj) in objects.iter_mut().enumerate() {
+        if obj.distance(x, y) <= FIREBALL_RADIUS as f32 && obj.fighter.is_some() {
+            let damage = spell_damage(FIREBALL_DAMAGE, &caster, obj, game).max(0);
+            game.messages.add(
+                format!(
+                    "The {} gets burned for {} magic hit points.",
+                    obj.name, damage
+                ),
+                DamageKind::Magical.color(),
+            );
+            if let Some(xp) = obj.take_damage(damage, game) {
+                // don't reward the player for burning themself!
+                if id != PLAYER {
+                    xp_to_gain += xp;
+                }
+            }
+        }
+    }
+    objects[PLAYER].gain_xp(xp_to_gain);
+
+    UseResult::UsedUp
+}
+
+/// find closest enemy, up to a maximum range, and in the player's FOV
+fn closest_monster(max_range: i32, game: &Game, objects: &[Object]) -> Option<usize> {
+    let mut closest_enemy = None;
+    let mut closest_dist = (max_range + 1) as f32; // start with (slightly more than) maximum range
+
+    for (id, object) in objects.iter().enumerate() {
+        if (id != PLAYER)
+            && object.fighter.is_some()
+            && object.ai.is_some()
+            && game.fov.is_in_fov(object.x, object.y)
+        {
+            // calculate distance between this object and the player
+            let dist = objects[PLAYER].distance_to(object);
+            if dist < closest_dist {
+                // it's closer, so remember it
+                closest_enemy = Some(id);
+                closest_dist = dist;
+            }
+        }
+    }
+    closest_enemy
+}
+
+/// the monster standing on (x, y), if it's visible and within range of the player
+fn target_monster(x: i32, y: i32, max_range: i32, game: &Game, objects: &[Object]) -> Option<usize> {
+    if !game.fov.is_in_fov(x, y) || objects[PLAYER].distance(x, y) > max_range as f32 {
+        return None;
+    }
+    objects
+        .iter()
+        .position(|obj| obj.pos() == (x, y) && obj.fighter.is_some() && obj.ai.is_some())
+        .filter(|&id| id != PLAYER)
+}
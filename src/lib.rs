@@ -0,0 +1,24 @@
+//! The rules of unkindred hearts: map, objects, FOV and turns.
+//! Nothing in here touches a tcod console, so it can run without a window.
+
+pub mod bsp;
+pub mod builder;
+pub mod caves;
+pub mod equipment;
+pub mod fov;
+pub mod game;
+pub mod item;
+pub mod look;
+pub mod map;
+pub mod messages;
+pub mod monsters;
+pub mod object;
+pub mod path;
+pub mod prefab;
+pub mod ranged;
+pub mod replay;
+pub mod save;
+pub mod schedule;
+pub mod status;
+pub mod targeting;
+pub mod void;
@@ -0,0 +1,92 @@
+use crate::game::{World, PLAYER};
+use crate::object::Object;
+use crate::void::{is_void_rift, VOID_RIFT_DESCRIPTION};
+
+/// What the look mode says about the tile at (x, y): who and what is there,
+/// how hurt they seem and what ails them, and the ground itself. Tiles out of
+/// view only tell what's remembered of them.
+pub fn describe_tile((x, y): (i32, i32), world: &World) -> Vec<String> {
+    let game = &world.game;
+    let tile = game.map[x as usize][y as usize];
+    if !tile.explored {
+        return vec!["You haven't seen what's there.".into()];
+    }
+    let ground = tile.kind.description();
+    if !game.fov.is_in_fov(x, y) {
+        let mut lines = vec![format!(
+            "You remember {} there, but you can't see it from here.",
+            ground
+        )];
+        // only what's drawn out of view, like the stairs, is remembered
+        lines.extend(
+            world
+                .objects
+                .iter()
+                .filter(|object| object.pos() == (x, y) && object.always_visible)
+                .map(|object| format!("You remember the {} there.", object.name)),
+        );
+        return lines;
+    }
+
+    // whoever stands there comes first, then whatever lies there
+    let mut here: Vec<usize> = (0..world.objects.len())
+        .filter(|&id| world.objects[id].pos() == (x, y))
+        .collect();
+    here.sort_by_key(|&id| !world.objects[id].blocks);
+
+    let mut lines = vec![];
+    for id in here {
+        let object = &world.objects[id];
+        if object.alive && object.fighter.is_some() {
+            describe_fighter(id, world, &mut lines);
+        } else if let Some(equipment) = object.equipment {
+            lines.push(format!("{} ({})", object.name, equipment.describe()));
+        } else if object.item.is_some_and(|item| item.stacks()) {
+            lines.push(format!("{} {}", object.count, object.name));
+        } else {
+            lines.push(object.name.clone());
+        }
+    }
+    lines.push(format!("Terrain: {}.", ground));
+    lines
+}
+
+/// its name and how hurt it looks, what it is, and what ails it
+fn describe_fighter(id: usize, world: &World, lines: &mut Vec<String>) {
+    let object = &world.objects[id];
+    let health = object.health_estimate(&world.game).unwrap_or_default();
+    if id == PLAYER {
+        lines.push(format!("You: {}", health));
+    } else {
+        lines.push(format!("{}: {}", object.name, health));
+        if let Some(description) = description(object, world) {
+            lines.push(description.into());
+        }
+    }
+
+    if !object.effects.is_empty() {
+        let effects: Vec<_> = object
+            .effects
+            .iter()
+            .map(|effect| {
+                let turns = if effect.turns == 1 { "turn" } else { "turns" };
+                format!("{} ({} {})", effect.kind.adjective(), effect.turns, turns)
+            })
+            .collect();
+        let who = if id == PLAYER { "You are" } else { "It is" };
+        lines.push(format!("{} {}.", who, effects.join(", ")));
+    }
+}
+
+/// the description of a monster, from its definition
+fn description<'a>(object: &Object, world: &'a World) -> Option<&'a str> {
+    if is_void_rift(object) {
+        return Some(VOID_RIFT_DESCRIPTION);
+    }
+    world
+        .monsters
+        .iter()
+        .find(|monster| monster.name == object.name)
+        .map(|monster| monster.description.as_str())
+        .filter(|description| !description.is_empty())
+}
@@ -1,102 +1,103 @@
-use object::DeathCallback;
-use object::Fighter;
-use object::ai_take_turn;
-use object::get_names_under_mouse;
+use std::time::Duration;
+
 use tcod::colors::*;
 use tcod::console::*;
-use tcod::input::KeyCode::*;
+use tcod::input::KeyCode::{self, *};
 use tcod::input::{self, Event, Key, Mouse};
 
-mod object;
-use object::Object;
-
-mod map;
-use map::{Map, make_map, render_map};
-use tcod::map::{FovAlgorithm, Map as FovMap};
+use unkindred_hearts::game::{level_up_xp, Command, PlayerAction, Stat, World, MAP_HEIGHT, MAP_WIDTH, PLAYER};
+use unkindred_hearts::look::describe_tile;
+use unkindred_hearts::monsters::{load_monsters, MonsterTemplate, MONSTERS_PATH};
+use unkindred_hearts::prefab::{load_prefabs, Prefab, PREFABS_PATH};
+use unkindred_hearts::replay::{Recorder, Replay};
+use unkindred_hearts::save::{load_game, save_game};
+use unkindred_hearts::targeting::{visible_enemies, Targeting};
 
 mod ui;
 
-use ui::{render_bar, Messages};
+use ui::{
+    draw_object, equipment_menu, get_names_under_mouse, inventory_menu, menu, msgbox, render_bar,
+    render_map,
+};
 
 
-// player will always be the first object
-const PLAYER: usize = 0;
 // actual size of the window
 const SCREEN_WIDTH: i32 = 80;
 const SCREEN_HEIGHT: i32 = 50;
 
-const LIMIT_FPS: i32 = 30; 
-// size of the map
-const MAP_WIDTH: i32 = 80;
-const MAP_HEIGHT: i32 = 43; 
+const LIMIT_FPS: i32 = 30;
 
 // sizes and coordinates relevant for the GUI
 const BAR_WIDTH: i32 = 20;
 const PANEL_HEIGHT: i32 = 7;
 const PANEL_Y: i32 = SCREEN_HEIGHT - PANEL_HEIGHT;
 
+// the player's status effects are listed between the bars and the messages
+const EFFECTS_X: i32 = BAR_WIDTH + 2;
+const EFFECTS_WIDTH: i32 = 12;
 
-const FOV_ALGO: FovAlgorithm = FovAlgorithm::Basic; // default FOV algorithm
-const FOV_LIGHT_WALLS: bool = true; // light walls or not
-const FOV_RADIUS: i32 = 8;
-
-const MSG_X: i32 = BAR_WIDTH + 2;
-const MSG_WIDTH: i32 = SCREEN_WIDTH - BAR_WIDTH - 2;
+const MSG_X: i32 = EFFECTS_X + EFFECTS_WIDTH;
+const MSG_WIDTH: i32 = SCREEN_WIDTH - MSG_X;
 const MSG_HEIGHT: usize = PANEL_HEIGHT as usize - 1;
 
+const LEVEL_SCREEN_WIDTH: i32 = 40;
+
+// width of the description shown by the look mode
+const LOOK_WIDTH: i32 = 30;
+
+// where the message log screen starts, leaving a line for its title, and how
+// many lines it has before the one listing its keys
+const LOG_TOP: i32 = 2;
+const LOG_HEIGHT: i32 = SCREEN_HEIGHT - LOG_TOP - 2;
+
+// how the line of fire and the area of effect are shown while targeting
+const TARGET_PATH_COLOR: Color = Color { r: 90, g: 90, b: 40 };
+const TARGET_AREA_COLOR: Color = Color { r: 120, g: 50, b: 20 };
+
+// where the run in progress is kept between sessions
+const SAVE_PATH: &str = "savegame";
+// where the current run is recorded, to be played back with --replay
+const REPLAY_PATH: &str = "replay";
+
+// how many frames a replay waits between commands, and how many it plays per
+// frame when fast-forwarding
+const REPLAY_STEP_FRAMES: i32 = 6;
+const REPLAY_FAST_STEPS: usize = 10;
+
+// how long a projectile is shown on each tile it flies through
+const SHOT_FRAME: Duration = Duration::from_millis(20);
+
 pub struct Tcod {
     root: Root,
     con: Offscreen,
     panel: Offscreen,
-    fov: FovMap,    
-    key: Key,  
+    key: Key,
     mouse: Mouse,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum PlayerAction {
-    TookTurn,
-    DidntTakeTurn,
-    Exit,
-}
-pub struct Game {
-    map: Map,
-    messages: Messages,
-}
+fn render_all(tcod: &mut Tcod, world: &World) {
+    let game = &world.game;
+    let objects = &world.objects;
 
-impl Game {
-    pub fn find_object(x: i32, y: i32, objects: &[Object]) -> Option<usize> {
-        // iterate through the objects array and return the first index that matches
-        objects.iter().position(|object| object.pos() == (x, y))
-    }
-}
-
-fn render_all(tcod: &mut Tcod, game: &mut Game, objects: &[Object], map: &mut Map, fov_recompute: bool) {
     // render the map
-    render_map(tcod, map, game);
-
-    if fov_recompute {
-        // recompute FOV if needed (the player moved or something)
-        let player = &objects[PLAYER];
-        tcod.fov
-            .compute_fov(player.x, player.y, FOV_RADIUS, FOV_LIGHT_WALLS, FOV_ALGO);
-    }
-
-    
+    render_map(&mut tcod.con, &game.map, &game.fov);
 
     let mut to_draw: Vec<_> = objects
     .iter()
-    .filter(|o| tcod.fov.is_in_fov(o.x, o.y))
+    .filter(|o| {
+        game.fov.is_in_fov(o.x, o.y)
+            || (o.always_visible && game.map[o.x as usize][o.y as usize].explored)
+    })
     .collect();
     // sort so that non-blocking objects come first
-    to_draw.sort_by(|o1, o2| o1.blocks.cmp(&o2.blocks));
+    to_draw.sort_by_key(|o| o.blocks);
     // draw the objects in the list
     for object in &to_draw {
-        object.draw(&mut tcod.con);
+        draw_object(object, &mut tcod.con);
     }
 
 
-    
+
     // blit the contents of "con" to the root console
     blit(
         &tcod.con,
@@ -114,7 +115,7 @@ fn render_all(tcod: &mut Tcod, game: &mut Game, objects: &[Object], map: &mut Ma
 
     // show the player's stats
     let hp = objects[PLAYER].fighter.map_or(0, |f| f.hp);
-    let max_hp = objects[PLAYER].fighter.map_or(0, |f| f.max_hp);
+    let max_hp = objects[PLAYER].max_hp(game);
     render_bar(
         &mut tcod.panel,
         1,
@@ -127,18 +128,71 @@ fn render_all(tcod: &mut Tcod, game: &mut Game, objects: &[Object], map: &mut Ma
         DARKER_RED,
     );
 
+    // show the player's experience towards the next level
+    let level = objects[PLAYER].level;
+    let xp = objects[PLAYER].fighter.map_or(0, |f| f.xp);
+    render_bar(
+        &mut tcod.panel,
+        1,
+        2,
+        BAR_WIDTH,
+        "XP",
+        xp,
+        level_up_xp(level),
+        LIGHT_BLUE,
+        DARKER_BLUE,
+    );
+
+    // list the player's status effects and how long they'll last
+    for (y, effect) in (1..PANEL_HEIGHT).zip(&objects[PLAYER].effects) {
+        tcod.panel.set_default_foreground(effect.kind.color());
+        tcod.panel.print_ex(
+            EFFECTS_X,
+            y,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            format!("{} {}", effect.kind.label(), effect.turns),
+        );
+    }
+
     // print the game messages, one line at a time
     let mut y = MSG_HEIGHT as i32;
-    for &(ref msg, color) in game.messages.iter().rev() {
-        let msg_height = tcod.panel.get_height_rect(MSG_X, y, MSG_WIDTH, 0, msg);
+    for message in game.messages.iter().rev() {
+        let msg = message.display();
+        let msg_height = tcod.panel.get_height_rect(MSG_X, y, MSG_WIDTH, 0, &msg);
         y -= msg_height;
         if y < 0 {
             break;
         }
-        tcod.panel.set_default_foreground(color);
+        tcod.panel.set_default_foreground(message.color);
         tcod.panel.print_rect(MSG_X, y, MSG_WIDTH, 0, msg);
     }
 
+    tcod.panel.set_default_foreground(LIGHT_GREY);
+    tcod.panel.print_ex(
+        1,
+        3,
+        BackgroundFlag::None,
+        TextAlignment::Left,
+        format!("Dungeon level: {}", game.dungeon_level),
+    );
+    tcod.panel.print_ex(
+        1,
+        4,
+        BackgroundFlag::None,
+        TextAlignment::Left,
+        format!("Player level: {}", level),
+    );
+
+    // show the seed so the run can be reproduced
+    tcod.panel.print_ex(
+        1,
+        PANEL_HEIGHT - 1,
+        BackgroundFlag::None,
+        TextAlignment::Left,
+        format!("Seed: {}", game.seed),
+    );
+
     // display names of objects under the mouse
     tcod.panel.set_default_foreground(LIGHT_GREY);
     tcod.panel.print_ex(
@@ -146,7 +200,7 @@ fn render_all(tcod: &mut Tcod, game: &mut Game, objects: &[Object], map: &mut Ma
         0,
         BackgroundFlag::None,
         TextAlignment::Left,
-        get_names_under_mouse(tcod.mouse, objects, &tcod.fov),
+        get_names_under_mouse(tcod.mouse, objects, &game.fov),
     );
 
     // blit the contents of `panel` to the root console
@@ -161,127 +215,606 @@ fn render_all(tcod: &mut Tcod, game: &mut Game, objects: &[Object], map: &mut Ma
     );
 }
 
-fn handle_keys(tcod: &mut Tcod, player: &mut Object, mut game: &mut Game, objects: &mut Vec<Object>) -> PlayerAction {
-    use PlayerAction::*;
-    let player_alive = objects[PLAYER].alive;
-    match (tcod.key, tcod.key.text(), player_alive) {
-        (Key { code: Enter, alt: true, .. }, _, _) => {
-            // Alt+Enter: toggle fullscreen
-            let fullscreen = tcod.root.is_fullscreen();
-            tcod.root.set_fullscreen(!fullscreen);
-            DidntTakeTurn
-        }
-        (Key { code: Escape, .. }, _, _) => Exit, // exit game
+/// A cursor over the map, shared by the targeting and look modes: the numpad
+/// moves it a tile at a time, the mouse moves it to wherever it points, and
+/// Tab jumps between the given monsters.
+struct MapCursor {
+    pos: (i32, i32),
+    monsters: Vec<usize>,
+    next_monster: usize,
+    mouse_at: (isize, isize),
+}
 
-        // movement keys
-        (Key { code: NumPad8, .. }, _, true) => {
-            player.player_move_or_attack(0, -1, &mut game, objects);
-            TookTurn
+impl MapCursor {
+    fn new(tcod: &Tcod, pos: (i32, i32), monsters: Vec<usize>) -> Self {
+        MapCursor {
+            pos,
+            monsters,
+            next_monster: 0,
+            mouse_at: (tcod.mouse.cx, tcod.mouse.cy),
         }
-        (Key { code: NumPad2, .. }, _, true) => {
-            player.player_move_or_attack(0, 1, &mut game, objects);
-            TookTurn
+    }
+
+    /// move to the next of the monsters, if there are any
+    fn next_monster(&mut self, world: &World) {
+        if !self.monsters.is_empty() {
+            self.pos = world.objects[self.monsters[self.next_monster % self.monsters.len()]].pos();
+            self.next_monster += 1;
         }
-        (Key { code: NumPad4, .. }, _, true) => {
-            player.player_move_or_attack(-1, 0, &mut game, objects);
-            TookTurn
+    }
+
+    /// Read this frame's input into `tcod.key` and `tcod.mouse`, and move
+    /// the cursor with it. Only a click made this frame is left in `tcod.mouse`.
+    fn update(&mut self, tcod: &mut Tcod, world: &World) {
+        tcod.mouse.lbutton_pressed = false;
+        tcod.mouse.rbutton_pressed = false;
+        let event = input::check_for_event(input::KEY_PRESS | input::MOUSE).map(|e| e.1);
+        match event {
+            Some(Event::Mouse(m)) => tcod.mouse = m,
+            Some(Event::Key(k)) => tcod.key = k,
+            None => tcod.key = Default::default(),
         }
-        (Key { code: NumPad6, .. }, _, true) => {
-            player.player_move_or_attack(1, 0, &mut game, objects);
-            TookTurn
+
+        // the cursor follows the mouse only when it moves, so it doesn't
+        // undo what the keys did
+        let on_map = |x: i32, y: i32| x >= 0 && y >= 0 && x < MAP_WIDTH && y < MAP_HEIGHT;
+        if (tcod.mouse.cx, tcod.mouse.cy) != self.mouse_at {
+            self.mouse_at = (tcod.mouse.cx, tcod.mouse.cy);
+            if on_map(self.mouse_at.0 as i32, self.mouse_at.1 as i32) {
+                self.pos = (self.mouse_at.0 as i32, self.mouse_at.1 as i32);
+            }
         }
-        (Key { code: NumPad7, .. }, _, true) => {
-            player.player_move_or_attack(-1, -1, &mut game, objects);
-            TookTurn
+        if tcod.key.code == Tab {
+            self.next_monster(world);
         }
-        (Key { code: NumPad9, .. }, _, true) => {
-            player.player_move_or_attack(1, -1, &mut game, objects);
-            TookTurn
+        if let Some((dx, dy)) = numpad_direction(tcod.key.code) {
+            let (x, y) = (self.pos.0 + dx, self.pos.1 + dy);
+            if on_map(x, y) {
+                self.pos = (x, y);
+            }
         }
-        (Key { code: NumPad1, .. }, _, true) => {
-            player.player_move_or_attack(-1, 1, &mut game, objects);
-            TookTurn
+    }
+}
+
+/// Let the player pick a target tile. The cursor starts on the closest enemy
+/// in reach; Tab cycles through the others, the numpad moves it a tile at a
+/// time and the mouse moves it to wherever it points. Enter or a left-click
+/// picks the tile, if it's allowed; Escape or a right-click cancels.
+fn target_tile(tcod: &mut Tcod, world: &World, targeting: Targeting) -> Option<(i32, i32)> {
+    let enemies = visible_enemies(&targeting, &world.game, &world.objects);
+    let mut pointer = MapCursor::new(tcod, world.objects[PLAYER].pos(), enemies);
+    pointer.next_monster(world);
+
+    loop {
+        pointer.update(tcod, world);
+        let cursor = pointer.pos;
+        let allowed = |tile| targeting.allows(tile, &world.game, &world.objects);
+
+        if tcod.mouse.rbutton_pressed || tcod.key.code == Escape {
+            return None;
         }
-        (Key { code: NumPad3, .. }, _, true) => {
-            player.player_move_or_attack(1, 1, &mut game, objects);
-            TookTurn
+        if (tcod.mouse.lbutton_pressed || matches!(tcod.key.code, Enter | NumPadEnter))
+            && allowed(cursor)
+        {
+            return Some(cursor);
         }
 
-        _ => DidntTakeTurn
+        // show the way there, where it would reach, and the cursor
+        render_all(tcod, world);
+        for (x, y) in targeting.path(cursor, &world.game, &world.objects) {
+            tcod.root.set_char_background(x, y, TARGET_PATH_COLOR, BackgroundFlag::Set);
+        }
+        if allowed(cursor) {
+            for (x, y) in targeting.area(cursor, &world.game) {
+                tcod.root.set_char_background(x, y, TARGET_AREA_COLOR, BackgroundFlag::Set);
+            }
+        }
+        let cursor_color = if allowed(cursor) { WHITE } else { RED };
+        tcod.root.set_char_background(cursor.0, cursor.1, cursor_color, BackgroundFlag::Set);
+        tcod.root.set_default_foreground(WHITE);
+        tcod.root.print_ex(
+            1,
+            0,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            "Tab: next target  NumPad/mouse: move  Enter/click: confirm  Esc: cancel",
+        );
+        tcod.root.flush();
     }
 }
-fn main() {
 
-    // create object representing the player
-    let mut player = Object::new(25, 23, '@', "player", YELLOW, true);
-    player.alive = true;
-    player.fighter = Some(Fighter {
-        max_hp: 30,
-        hp: 30,
-        defense: 2,
-        power: 5,
-        magic_defense: 0,
-        magic: 0,
-        on_death: DeathCallback::Player
+/// Move a cursor over the map and describe whatever is under it, until Escape
+/// (or L again) is pressed. Like targeting, Tab jumps between the monsters in
+/// sight and the numpad or the mouse move the cursor.
+fn look(tcod: &mut Tcod, world: &World) {
+    let everywhere = Targeting {
+        range: None,
+        projectile: false,
+        radius: 0,
+    };
+    let monsters = visible_enemies(&everywhere, &world.game, &world.objects);
+    let mut pointer = MapCursor::new(tcod, world.objects[PLAYER].pos(), monsters);
+
+    loop {
+        pointer.update(tcod, world);
+        let cursor = pointer.pos;
+        if tcod.key.code == Escape || tcod.key.text() == "l" || tcod.root.window_closed() {
+            return;
+        }
 
-    });
-    // force FOV "recompute" first time through the game loop
-    let mut previous_player_position = (-1, -1);
+        render_all(tcod, world);
+        tcod.root.set_char_background(cursor.0, cursor.1, WHITE, BackgroundFlag::Set);
+        tcod.root.set_default_foreground(WHITE);
+        tcod.root.print_ex(
+            1,
+            0,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            "Looking around. Tab: next monster  NumPad/mouse: move  Esc: done",
+        );
+
+        // the description goes on the side of the map away from the cursor
+        let text = describe_tile(cursor, world).join("\n");
+        let x = if cursor.0 < MAP_WIDTH / 2 {
+            MAP_WIDTH - LOOK_WIDTH - 1
+        } else {
+            1
+        };
+        let height = tcod.root.get_height_rect(x, 2, LOOK_WIDTH, MAP_HEIGHT, &text);
+        tcod.root.set_default_background(BLACK);
+        tcod.root.rect(x - 1, 1, LOOK_WIDTH + 2, height + 2, false, BackgroundFlag::Set);
+        tcod.root.print_rect(x, 2, LOOK_WIDTH, height, &text);
+        tcod.root.flush();
+    }
+}
 
-     // the list of objects
-    let mut objects = vec![player];
+/// Show the whole message log, a page at a time, with the turn each message
+/// was said on. The arrows (or 8 and 2 on the numpad) scroll a line, PageUp
+/// and PageDown a page, Home and End go to the oldest and newest messages, and
+/// `/` searches the log. Escape clears the search, then leaves.
+fn message_log(tcod: &mut Tcod, world: &World) {
+    let mut query = String::new();
+    let mut typing = false;
+    // the first message on screen; None keeps the newest ones in view
+    let mut top: Option<usize> = None;
+
+    loop {
+        match input::check_for_event(input::KEY_PRESS) {
+            Some((_, Event::Key(k))) => tcod.key = k,
+            _ => tcod.key = Default::default(),
+        }
+        if tcod.root.window_closed() {
+            return;
+        }
 
-    let mut map = make_map(MAP_WIDTH, MAP_HEIGHT, &mut objects);
+        let lines: Vec<(String, Color)> = world
+            .game
+            .messages
+            .search(&query)
+            .map(|message| {
+                let text = format!("Turn {}: {}", message.turn, message.display());
+                (text, message.color)
+            })
+            .collect();
+        let heights: Vec<i32> = lines
+            .iter()
+            .map(|(text, _)| tcod.root.get_height_rect(1, 0, SCREEN_WIDTH - 2, SCREEN_HEIGHT, text))
+            .collect();
+        let last_page = log_page_start(&heights, lines.len());
+        let first = top.unwrap_or(last_page).min(last_page);
+
+        if typing {
+            match (tcod.key.code, tcod.key.text()) {
+                (Enter, _) => typing = false,
+                (Escape, _) => {
+                    typing = false;
+                    query.clear();
+                }
+                (Backspace, _) => {
+                    query.pop();
+                }
+                (Text, text) => query.push_str(text),
+                _ => {}
+            }
+            top = None;
+        } else {
+            match (tcod.key.code, tcod.key.text()) {
+                (Escape, _) if !query.is_empty() => {
+                    query.clear();
+                    top = None;
+                }
+                (Escape, _) | (Text, "m") => return,
+                (Up | NumPad8, _) => top = Some(first.saturating_sub(1)),
+                (Down | NumPad2, _) => top = Some(first + 1),
+                (PageUp | NumPad9, _) => top = Some(log_page_start(&heights, first)),
+                (PageDown | NumPad3, _) => top = Some(first + log_page_len(&heights, first)),
+                (Home | NumPad7, _) => top = Some(0),
+                (End | NumPad1, _) => top = None,
+                (Text, "/") => {
+                    typing = true;
+                    query.clear();
+                }
+                _ => {}
+            }
+        }
 
-    let mut game = Game {
-        map: map.clone(),
-        messages: Messages::new()
-    };
+        tcod.root.set_default_background(BLACK);
+        tcod.root.clear();
+        let shown = log_page_len(&heights, first);
+        tcod.root.set_default_foreground(WHITE);
+        let title = if typing {
+            format!("Search: {}_", query)
+        } else if lines.is_empty() {
+            "Message log: nothing to show".into()
+        } else {
+            format!("Message log: {}-{} of {}", first + 1, first + shown, lines.len())
+        };
+        tcod.root.print_ex(1, 0, BackgroundFlag::None, TextAlignment::Left, title);
+        if !query.is_empty() && !typing {
+            let filter = format!("(only those with \"{}\")", query);
+            tcod.root.print_ex(
+                SCREEN_WIDTH - 2,
+                0,
+                BackgroundFlag::None,
+                TextAlignment::Right,
+                filter,
+            );
+        }
 
-    
+        let mut y = LOG_TOP;
+        for ((text, color), height) in lines.iter().zip(&heights).skip(first).take(shown) {
+            tcod.root.set_default_foreground(*color);
+            tcod.root.print_rect(1, y, SCREEN_WIDTH - 2, 0, text);
+            y += height;
+        }
 
+        tcod.root.set_default_foreground(LIGHT_GREY);
+        tcod.root.print_ex(
+            1,
+            SCREEN_HEIGHT - 1,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            "Up/Down: scroll  PgUp/PgDn: page  Home/End: oldest/newest  /: search  Esc: back",
+        );
+        tcod.root.flush();
+    }
+}
 
+/// how many of the messages from `first` on fit on a page of the log
+fn log_page_len(heights: &[i32], first: usize) -> usize {
+    let mut used = 0;
+    let mut count = 0;
+    for height in &heights[first..] {
+        if used + height > LOG_HEIGHT {
+            break;
+        }
+        used += height;
+        count += 1;
+    }
+    count
+}
 
-    let con = Offscreen::new(MAP_WIDTH, MAP_HEIGHT);
+/// the first message of the page of the log that ends just before `end`
+fn log_page_start(heights: &[i32], end: usize) -> usize {
+    let mut used = 0;
+    let mut start = end;
+    while start > 0 && used + heights[start - 1] <= LOG_HEIGHT {
+        start -= 1;
+        used += heights[start];
+    }
+    start
+}
 
+/// the way a numpad key points, if it's one of the direction keys
+fn numpad_direction(code: KeyCode) -> Option<(i32, i32)> {
+    match code {
+        NumPad8 => Some((0, -1)),
+        NumPad2 => Some((0, 1)),
+        NumPad4 => Some((-1, 0)),
+        NumPad6 => Some((1, 0)),
+        NumPad7 => Some((-1, -1)),
+        NumPad9 => Some((1, -1)),
+        NumPad1 => Some((-1, 1)),
+        NumPad3 => Some((1, 1)),
+        _ => None,
+    }
+}
 
+/// ask for a direction with the numpad, or None if any other key is pressed
+fn ask_direction(tcod: &mut Tcod, world: &World, prompt: &str) -> Option<(i32, i32)> {
+    render_all(tcod, world);
+    tcod.root.set_default_foreground(WHITE);
+    tcod.root.print_ex(1, 0, BackgroundFlag::None, TextAlignment::Left, prompt);
+    tcod.root.flush();
+    let key = tcod.root.wait_for_keypress(true);
+    numpad_direction(key.code)
+}
 
-    let root = Root::initializer()
-    .font("assets/terminal8x8_gs_ro.png", FontLayout::AsciiInRow)
-    .font_type(FontType::Greyscale)
-    .size(SCREEN_WIDTH, SCREEN_HEIGHT)
-    .title("unkindred hearts")
-    .init();
+/// the value after `flag` on the command line, if the flag was given
+fn arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return Some(args.next().unwrap_or_default());
+        }
+    }
+    None
+}
 
-    let mut tcod = Tcod { root, con, fov: FovMap::new(MAP_WIDTH, MAP_HEIGHT) , panel: Offscreen::new(SCREEN_WIDTH, SCREEN_HEIGHT), key: Default::default(),
-        mouse: Default::default(), };
+/// read the `--seed <number>` flag from the command line, if it was given
+fn seed_from_args() -> Option<u64> {
+    let value = arg_value("--seed")?;
+    match value.parse() {
+        Ok(seed) => Some(seed),
+        Err(_) => {
+            eprintln!("--seed expects a number, got {:?}", value);
+            std::process::exit(1);
+        }
+    }
+}
 
-    
+/// read the `--max-messages <number>` flag, how much of the message log is kept
+fn max_messages_from_args() -> Option<usize> {
+    let value = arg_value("--max-messages")?;
+    match value.parse() {
+        Ok(max_messages) if max_messages > 0 => Some(max_messages),
+        _ => {
+            eprintln!("--max-messages expects a number above 0, got {:?}", value);
+            std::process::exit(1);
+        }
+    }
+}
 
-    for y in 0..MAP_HEIGHT {
-        for x in 0..MAP_WIDTH {
-            tcod.fov.set(
-                x,
-                y,
-                !game.map[x as usize][y as usize].block_sight,
-                !game.map[x as usize][y as usize].blocked,
+fn handle_keys(tcod: &mut Tcod, world: &mut World) -> PlayerAction {
+    use PlayerAction::*;
+    let player_alive = world.objects[PLAYER].alive;
+    let command = match (tcod.key, tcod.key.text(), player_alive) {
+        (Key { code: Enter, alt: true, .. }, _, _) => {
+            // Alt+Enter: toggle fullscreen
+            let fullscreen = tcod.root.is_fullscreen();
+            tcod.root.set_fullscreen(!fullscreen);
+            return DidntTakeTurn;
+        }
+        (Key { code: Escape, .. }, _, _) => return Exit, // exit game
+
+        // movement keys
+        (Key { code: NumPad8, .. }, _, true) => Command::Move(0, -1),
+        (Key { code: NumPad2, .. }, _, true) => Command::Move(0, 1),
+        (Key { code: NumPad4, .. }, _, true) => Command::Move(-1, 0),
+        (Key { code: NumPad6, .. }, _, true) => Command::Move(1, 0),
+        (Key { code: NumPad7, .. }, _, true) => Command::Move(-1, -1),
+        (Key { code: NumPad9, .. }, _, true) => Command::Move(1, -1),
+        (Key { code: NumPad1, .. }, _, true) => Command::Move(-1, 1),
+        (Key { code: NumPad3, .. }, _, true) => Command::Move(1, 1),
+        (Key { code: NumPad5, .. }, _, true) => Command::Wait,
+
+        (Key { code: Text, .. }, "g", true) => Command::PickUp,
+        (Key { code: Text, .. }, "<", true) => Command::Descend,
+        (Key { code: Text, .. }, "c", true) => {
+            match ask_direction(tcod, world, "Close the door in which direction? (numpad)") {
+                Some((dx, dy)) => Command::CloseDoor(dx, dy),
+                None => return DidntTakeTurn,
+            }
+        }
+        (Key { code: Text, .. }, "i", true) => {
+            // show the inventory: if an item is selected, use it
+            let inventory_index = inventory_menu(
+                &world.game.inventory,
+                "Press the key next to an item to use it, or any other to cancel.\n",
+                &mut tcod.root,
             );
+            let inventory_index = match inventory_index {
+                Some(index) => index,
+                None => return DidntTakeTurn,
+            };
+            let targeting = world.game.inventory[inventory_index]
+                .item
+                .and_then(Targeting::item);
+            let target = match targeting {
+                Some(targeting) => match target_tile(tcod, world, targeting) {
+                    Some(tile) => Some(tile),
+                    None => return DidntTakeTurn,
+                },
+                None => None,
+            };
+            Command::UseItem(inventory_index, target)
+        }
+        (Key { code: Text, .. }, "f", true) => {
+            // pick something to shoot at with the equipped ranged weapon, if
+            // there's anything to shoot with
+            let targeting = match Targeting::fire(&world.game, &world.objects) {
+                Ok(targeting) => targeting,
+                Err(reason) => {
+                    world.game.messages.add(reason, RED);
+                    return DidntTakeTurn;
+                }
+            };
+            match target_tile(tcod, world, targeting) {
+                Some((x, y)) => Command::Fire(x, y),
+                None => return DidntTakeTurn,
+            }
+        }
+        (Key { code: Text, .. }, "l", _) => {
+            look(tcod, world);
+            return DidntTakeTurn;
+        }
+        (Key { code: Text, .. }, "m", _) => {
+            message_log(tcod, world);
+            return DidntTakeTurn;
         }
+        (Key { code: Text, .. }, "e", true) => {
+            // show what the player has on; if a slot is chosen, take it off
+            match equipment_menu(&world.objects[PLAYER], &world.game, &mut tcod.root) {
+                Some(index) => Command::UseItem(index, None),
+                None => return DidntTakeTurn,
+            }
+        }
+        (Key { code: Text, .. }, "d", true) => {
+            // show the inventory; if an item is selected, drop it
+            let inventory_index = inventory_menu(
+                &world.game.inventory,
+                "Press the key next to an item to drop it, or any other to cancel.\n",
+                &mut tcod.root,
+            );
+            match inventory_index {
+                Some(index) => Command::Drop(index),
+                None => return DidntTakeTurn,
+            }
+        }
+
+        _ => return DidntTakeTurn,
+    };
+    let player_action = world.step(command);
+    animate_shots(tcod, world);
+    player_action
+}
+
+/// show whatever was shot during the last step flying across the map
+fn animate_shots(tcod: &mut Tcod, world: &World) {
+    for shot in &world.game.shots {
+        for &(x, y) in &shot.path {
+            if !world.game.fov.is_in_fov(x, y) {
+                continue;
+            }
+            render_all(tcod, world);
+            tcod.root.put_char_ex(x, y, shot.glyph, shot.color, BLACK);
+            tcod.root.flush();
+            std::thread::sleep(SHOT_FRAME);
+        }
+    }
+}
+/// let the player spend every level-up they have earned
+fn level_up(tcod: &mut Tcod, world: &mut World) {
+    while world.can_level_up() {
+        let fighter = world.objects[PLAYER].fighter.unwrap();
+        let options = [
+            format!("Constitution (+10 HP, from {})", fighter.max_hp),
+            format!("Strength (+1 attack, from {})", fighter.power),
+            format!("Agility (+1 defense, from {})", fighter.defense),
+        ];
+        // keep asking until a choice is made
+        let choice = menu(
+            "Level up! Choose a stat to raise:\n",
+            &options,
+            LEVEL_SCREEN_WIDTH,
+            &mut tcod.root,
+        );
+        let stat = match choice {
+            Some(0) => Stat::MaxHp,
+            Some(1) => Stat::Power,
+            Some(2) => Stat::Defense,
+            _ if tcod.root.window_closed() => return,
+            _ => continue,
+        };
+        world.step(Command::LevelUp(stat));
     }
+}
 
-    let mut player_action = PlayerAction::DidntTakeTurn; // Declare and initialize player_action before the loop
+fn new_game(seed: u64, monsters: &[MonsterTemplate], prefabs: &[Prefab]) -> World {
+    let mut world = World::new(seed, monsters.to_vec(), prefabs.to_vec());
 
     // a warm welcoming message!
-    game.messages.add("Welcome, unkindred soul. the void is trying to source of the Fostering Benevolence!", YELLOW);
-    game.messages.add("this will spread the void to the rest of the world! save us all before it!", YELLOW);
+    world.game.messages.add("Welcome, unkindred soul. the void is trying to source of the Fostering Benevolence!", YELLOW);
+    world.game.messages.add("this will spread the void to the rest of the world! save us all before it!", YELLOW);
+    world
+}
+
+/// start recording the run, or pick its recording back up if it was continued
+fn record_replay(world: &mut World, continued: bool) {
+    let recorder = if continued {
+        Recorder::resume(REPLAY_PATH, world.game.seed, world.game.commands)
+    } else {
+        Recorder::create(REPLAY_PATH, world.game.seed)
+    };
+    match recorder {
+        Ok(recorder) => world.recorder = Some(recorder),
+        Err(e) => world
+            .game
+            .messages
+            .add(format!("This run won't be recorded: {}", e), RED),
+    }
+}
+
+/// play a recorded run back, command by command, until Escape is pressed
+fn play_replay(tcod: &mut Tcod, replay: &Replay, monsters: &[MonsterTemplate], prefabs: &[Prefab]) {
+    let mut world = new_game(replay.seed, monsters, prefabs);
+    let mut played = 0;
+    let mut paused = false;
+    let mut fast_forward = false;
+    let mut wait = REPLAY_STEP_FRAMES;
 
-    render_all(&mut tcod, &mut game, &objects, &mut map, true);
-    tcod::system::set_fps(LIMIT_FPS);
     while !tcod.root.window_closed() {
         tcod.con.set_default_foreground(WHITE);
         tcod.con.clear();
 
-        let fov_recompute = previous_player_position != (objects[PLAYER].pos());
+        match input::check_for_event(input::MOUSE | input::KEY_PRESS) {
+            Some((_, Event::Mouse(m))) => tcod.mouse = m,
+            Some((_, Event::Key(k))) => tcod.key = k,
+            _ => tcod.key = Default::default(),
+        }
+
+        // work out how many commands to play this frame
+        let mut steps = 0;
+        match (tcod.key, tcod.key.text()) {
+            (Key { code: Escape, .. }, _) => break,
+            (Key { code: Spacebar, .. }, _) => paused = !paused,
+            (Key { code: Text, .. }, "f") => fast_forward = !fast_forward,
+            // step through one command at a time while paused
+            (Key { code: Right, .. }, _) if paused => steps = 1,
+            _ => {}
+        }
+        if !paused {
+            if fast_forward {
+                steps = REPLAY_FAST_STEPS;
+            } else if wait == 0 {
+                steps = 1;
+                wait = REPLAY_STEP_FRAMES;
+            } else {
+                wait -= 1;
+            }
+        }
+        for &command in replay.commands.iter().skip(played).take(steps) {
+            world.step(command);
+            played += 1;
+            if !fast_forward {
+                animate_shots(tcod, &world);
+            }
+        }
+
+        render_all(tcod, &world);
+
+        // show where the replay is at, over the top of the map
+        let state = if played == replay.commands.len() {
+            "end"
+        } else if paused {
+            "paused"
+        } else if fast_forward {
+            "fast"
+        } else {
+            "playing"
+        };
+        tcod.root.set_default_foreground(WHITE);
+        tcod.root.print_ex(
+            1,
+            0,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            format!(
+                "Replay {}/{} ({})  Space: pause  Right: step  F: fast-forward  Esc: quit",
+                played,
+                replay.commands.len(),
+                state
+            ),
+        );
+        tcod.root.flush();
+    }
+}
+
+fn play_game(tcod: &mut Tcod, world: &mut World) {
+    render_all(tcod, world);
+    while !tcod.root.window_closed() {
+        tcod.con.set_default_foreground(WHITE);
+        tcod.con.clear();
 
         match input::check_for_event(input::MOUSE | input::KEY_PRESS) {
             Some((_, Event::Mouse(m))) => tcod.mouse = m,
@@ -289,31 +822,132 @@ fn main() {
             _ => tcod.key = Default::default(),
         }
 
-        render_all(&mut tcod, &mut game, &objects, &mut map, fov_recompute);
+        render_all(tcod, world);
 
         tcod.root.flush();
         tcod.root.wait_for_keypress(true);
         // handle keys and exit game if needed
-        previous_player_position = objects[PLAYER].pos();
-        {
-            let mut player_clone = objects[PLAYER].clone();
-            player_action = handle_keys(&mut tcod, &mut player_clone, &mut game, &mut objects);
-            if player_action == PlayerAction::Exit {
-                break;
-            }
+        let player_action = handle_keys(tcod, world);
+        if player_action == PlayerAction::Exit {
+            break;
         }
 
-        // let monsters take their turn
-        if objects[PLAYER].alive && player_action != PlayerAction::DidntTakeTurn {
-            for id in 0..objects.len() {
-                // only if object is not player
-                if id != PLAYER {
-                    let object = &mut objects[id];
-                    if object.ai.is_some() {
-                        ai_take_turn(id, &tcod, &mut game, &mut objects);
-                    }
+        // the player may have earned enough XP to level up
+        level_up(tcod, world);
+    }
+
+    // keep the run for next time, unless it's over
+    if world.objects[PLAYER].alive {
+        if let Err(e) = save_game(world, SAVE_PATH) {
+            msgbox(&format!("\nCould not save the game: {}\n", e), 24, &mut tcod.root);
+        }
+    } else {
+        let _ = std::fs::remove_file(SAVE_PATH);
+    }
+}
+
+fn main_menu(
+    tcod: &mut Tcod,
+    seed: Option<u64>,
+    max_messages: Option<usize>,
+    monsters: &[MonsterTemplate],
+    prefabs: &[Prefab],
+) {
+    while !tcod.root.window_closed() {
+        tcod.root.clear();
+
+        // show the game's title
+        tcod.root.set_default_foreground(LIGHT_YELLOW);
+        tcod.root.print_ex(
+            SCREEN_WIDTH / 2,
+            SCREEN_HEIGHT / 2 - 4,
+            BackgroundFlag::None,
+            TextAlignment::Center,
+            "UNKINDRED HEARTS",
+        );
+
+        // only offer to continue if there is something to continue
+        let can_continue = std::path::Path::new(SAVE_PATH).exists();
+        let mut choices = vec!["Play a new game"];
+        if can_continue {
+            choices.push("Continue");
+        }
+        choices.push("Quit");
+
+        let choice = menu("", &choices, 24, &mut tcod.root).map(|i| choices[i]);
+        match choice {
+            Some("Play a new game") => {
+                let mut world = new_game(seed.unwrap_or_else(rand::random), monsters, prefabs);
+                if let Some(max_messages) = max_messages {
+                    world.game.messages.set_max_messages(max_messages);
                 }
+                record_replay(&mut world, false);
+                play_game(tcod, &mut world);
             }
+            Some("Continue") => match load_game(SAVE_PATH, monsters.to_vec(), prefabs.to_vec()) {
+                Ok(mut world) => {
+                    if let Some(max_messages) = max_messages {
+                        world.game.messages.set_max_messages(max_messages);
+                    }
+                    record_replay(&mut world, true);
+                    play_game(tcod, &mut world);
+                }
+                Err(e) => {
+                    msgbox(&format!("\nCould not continue the last game:\n{}\n", e), 40, &mut tcod.root);
+                }
+            },
+            Some("Quit") => break,
+            _ => {}
+        }
+    }
+}
+
+fn main() {
+    // a seed from the command line is used for new games; otherwise one is picked at random
+    let seed = seed_from_args();
+    // how many messages the log keeps, if not the default
+    let max_messages = max_messages_from_args();
+    // with --replay, a recorded run is played back instead of the main menu
+    let replay = arg_value("--replay").map(|path| match Replay::load(path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("could not play the replay: {}", e);
+            std::process::exit(1);
         }
+    });
+
+    // check the game data before opening a window, so mistakes are easy to read
+    let monsters = match load_monsters(MONSTERS_PATH) {
+        Ok(monsters) => monsters,
+        Err(e) => {
+            eprintln!("error in game data: {}", e);
+            std::process::exit(1);
+        }
+    };
+    // prefabs are checked against the monsters, so they're loaded second
+    let prefabs = match load_prefabs(PREFABS_PATH, &monsters) {
+        Ok(prefabs) => prefabs,
+        Err(e) => {
+            eprintln!("error in game data: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let con = Offscreen::new(MAP_WIDTH, MAP_HEIGHT);
+
+    let root = Root::initializer()
+    .font("assets/terminal8x8_gs_ro.png", FontLayout::AsciiInRow)
+    .font_type(FontType::Greyscale)
+    .size(SCREEN_WIDTH, SCREEN_HEIGHT)
+    .title("unkindred hearts")
+    .init();
+
+    let mut tcod = Tcod { root, con, panel: Offscreen::new(SCREEN_WIDTH, SCREEN_HEIGHT), key: Default::default(),
+        mouse: Default::default(), };
+
+    tcod::system::set_fps(LIMIT_FPS);
+    match replay {
+        Some(replay) => play_replay(&mut tcod, &replay, &monsters, &prefabs),
+        None => main_menu(&mut tcod, seed, max_messages, &monsters, &prefabs),
     }
 }
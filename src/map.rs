@@ -1,170 +1,415 @@
+use serde::{Deserialize, Serialize};
 use tcod::colors::*;
-use tcod::console::*;
 use std::cmp;
+use std::collections::VecDeque;
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::Rng;
 
-use crate::Game;
-use crate::Tcod;
-use crate::object::Ai;
-use crate::object::DeathCallback;
-use crate::object::{Object, is_blocked, Fighter};
-
-const COLOR_DARK_WALL: Color = Color { r: 0, g: 0, b: 100 };
-const COLOR_LIGHT_WALL: Color = Color {
-    r: 130,
-    g: 110,
-    b: 50,
-};
-const COLOR_DARK_GROUND: Color = Color {
-    r: 50,
-    g: 50,
-    b: 150,
-};
-const COLOR_LIGHT_GROUND: Color = Color {
-    r: 200,
-    g: 180,
-    b: 50,
+use crate::bsp::Bsp;
+use crate::builder::{
+    BuildData, BuilderChain, Doors, Doorways, FarthestApart, FirstAndLastRoom, MapBuilder, Pillars,
+    Spawns, Terrain,
 };
+use crate::caves::Caves;
+use crate::game::{GameRng, PLAYER};
+use crate::item::Item;
+use crate::monsters::MonsterTemplate;
+use crate::object::{Object, is_blocked};
+use crate::prefab::{Prefab, Vaults};
+use crate::schedule::ACTION_ENERGY;
+use crate::void::VoidRifts;
 
 //parameters for dungeon generator
 const ROOM_MAX_SIZE: i32 = 10;
 const ROOM_MIN_SIZE: i32 = 6;
 const MAX_ROOMS: i32 = 30;
 
+// damage done to whoever steps on lava
+const LAVA_DAMAGE: i32 = 6;
+
+
+
+/// What a tile of the map is made of, which decides how it looks, whether it
+/// can be walked or seen through, and what happens to whoever steps on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TileKind {
+    Floor,
+    Wall,
+    /// a closed door; walking into it opens it
+    Door,
+    OpenDoor,
+    /// deep enough to wade through slowly
+    Water,
+    /// burns whoever steps in it
+    Lava,
+    /// broken stone that's slow to climb over
+    Rubble,
+    /// floor the void has seeped into; it wears the player down and makes
+    /// monsters standing on it stronger
+    VoidFloor,
+    /// a drop too deep to cross, though it can be seen over
+    Chasm,
+}
+
+impl TileKind {
+    /// every kind of tile, for looking them up by glyph
+    const ALL: [TileKind; 9] = [
+        TileKind::Floor,
+        TileKind::Wall,
+        TileKind::Door,
+        TileKind::OpenDoor,
+        TileKind::Water,
+        TileKind::Lava,
+        TileKind::Rubble,
+        TileKind::VoidFloor,
+        TileKind::Chasm,
+    ];
+
+    /// the character it's drawn with; no two kinds share one
+    pub fn glyph(self) -> char {
+        match self {
+            TileKind::Floor => '.',
+            TileKind::Wall => '#',
+            TileKind::Door => '+',
+            TileKind::OpenDoor => '\'',
+            TileKind::Water => '~',
+            TileKind::Lava => '=',
+            TileKind::Rubble => ';',
+            TileKind::VoidFloor => ',',
+            TileKind::Chasm => ':',
+        }
+    }
+
+    /// what it is, as the look mode puts it
+    pub fn description(self) -> &'static str {
+        match self {
+            TileKind::Floor => "bare stone floor",
+            TileKind::Wall => "a solid stone wall",
+            TileKind::Door => "a closed door",
+            TileKind::OpenDoor => "an open door",
+            TileKind::Water => "deep water, slow to wade through",
+            TileKind::Lava => "burning lava",
+            TileKind::Rubble => "loose rubble, slow to climb over",
+            TileKind::VoidFloor => "floor tainted by the void",
+            TileKind::Chasm => "a chasm too deep to cross",
+        }
+    }
+
+    /// the kind drawn with `glyph`, if there is one
+    pub fn from_glyph(glyph: char) -> Option<TileKind> {
+        TileKind::ALL.into_iter().find(|kind| kind.glyph() == glyph)
+    }
+
+    /// the color it's drawn in while it's in view
+    pub fn light_color(self) -> Color {
+        match self {
+            TileKind::Floor | TileKind::OpenDoor => Color { r: 200, g: 180, b: 50 },
+            TileKind::Wall | TileKind::Door => Color { r: 130, g: 110, b: 50 },
+            TileKind::Water => Color { r: 50, g: 120, b: 255 },
+            TileKind::Lava => Color { r: 255, g: 90, b: 20 },
+            TileKind::Rubble => Color { r: 160, g: 140, b: 110 },
+            TileKind::VoidFloor => Color { r: 170, g: 60, b: 230 },
+            TileKind::Chasm => Color { r: 90, g: 90, b: 90 },
+        }
+    }
+
+    /// the color it's drawn in once explored but out of view
+    pub fn dark_color(self) -> Color {
+        match self {
+            TileKind::Floor | TileKind::OpenDoor => Color { r: 50, g: 50, b: 150 },
+            TileKind::Wall | TileKind::Door => Color { r: 0, g: 0, b: 100 },
+            TileKind::Water => Color { r: 20, g: 40, b: 120 },
+            TileKind::Lava => Color { r: 110, g: 40, b: 20 },
+            TileKind::Rubble => Color { r: 50, g: 45, b: 90 },
+            TileKind::VoidFloor => Color { r: 70, g: 20, b: 110 },
+            TileKind::Chasm => Color { r: 20, g: 20, b: 60 },
+        }
+    }
+
+    pub fn blocks_movement(self) -> bool {
+        matches!(self, TileKind::Wall | TileKind::Door | TileKind::Chasm)
+    }
+
+    pub fn blocks_sight(self) -> bool {
+        matches!(self, TileKind::Wall | TileKind::Door)
+    }
+
+    /// how much energy a step onto it takes; a normal step is `ACTION_ENERGY`
+    pub fn move_cost(self) -> i32 {
+        match self {
+            TileKind::Water => ACTION_ENERGY * 2,
+            TileKind::Rubble => ACTION_ENERGY * 3 / 2,
+            _ => ACTION_ENERGY,
+        }
+    }
+
+    /// whether stepping on it hurts, so it's best walked around
+    pub fn is_hazard(self) -> bool {
+        self.contact_damage() > 0
+    }
 
+    /// the damage done to whoever steps on it
+    pub fn contact_damage(self) -> i32 {
+        match self {
+            TileKind::Lava => LAVA_DAMAGE,
+            _ => 0,
+        }
+    }
+
+    /// how it hurts whoever steps on it, as in "<name> is burned by the lava"
+    pub fn contact_verb(self) -> &'static str {
+        match self {
+            TileKind::Lava => "is burned by the lava",
+            _ => "is hurt by the ground",
+        }
+    }
+}
 
 /// A tile of the map and its properties
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Tile {
-    pub blocked: bool,
+    pub kind: TileKind,
     pub explored: bool,
-    pub block_sight: bool,
 }
 
-
 impl Tile {
-    pub fn empty() -> Self {
+    pub fn new(kind: TileKind) -> Self {
         Tile {
-            blocked: false,
+            kind,
             explored: false,
-            block_sight: false,
         }
     }
 
+    pub fn empty() -> Self {
+        Tile::new(TileKind::Floor)
+    }
+
     pub fn wall() -> Self {
-        Tile {
-            blocked: true,
-            explored: false,
-            block_sight: true,
-        }
+        Tile::new(TileKind::Wall)
+    }
+
+    /// a closed door
+    pub fn door() -> Self {
+        Tile::new(TileKind::Door)
+    }
+
+    pub fn blocked(&self) -> bool {
+        self.kind.blocks_movement()
+    }
+
+    pub fn block_sight(&self) -> bool {
+        self.kind.blocks_sight()
+    }
+
+    pub fn is_door(&self) -> bool {
+        matches!(self.kind, TileKind::Door | TileKind::OpenDoor)
+    }
+
+    pub fn is_closed_door(&self) -> bool {
+        self.kind == TileKind::Door
+    }
+
+    pub fn is_open_door(&self) -> bool {
+        self.kind == TileKind::OpenDoor
+    }
+
+    /// whether it can be walked through, if any door in the way is opened first
+    pub fn passable(&self) -> bool {
+        !self.blocked() || self.is_closed_door()
     }
 }
 
-pub(crate) type Map = Vec<Vec<Tile>>;
+pub type Map = Vec<Vec<Tile>>;
+
+/// The ways a floor can be laid out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MapStyle {
+    /// rectangular rooms scattered at random, joined by tunnels
+    Rooms,
+    /// rooms spread evenly by splitting the map up, joined along the splits
+    Bsp,
+    /// organic caves grown by a cellular automaton
+    Caves,
+}
 
-pub(crate) fn make_map(width: i32, height: i32, objects: &mut Vec<Object>) -> Map {
-    // fill map with "unblocked" tiles
-    let mut map = vec![vec![Tile::wall(); height as usize]; width as usize];
+/// pick how the floor at `level` is laid out: the deeper it is, the more the
+/// void has worn its rooms away into caves
+pub fn map_style(level: u32, rng: &mut GameRng) -> MapStyle {
+    let cave_chance = from_dungeon_level(
+        &[
+            Transition { level: 3, value: 25 },
+            Transition { level: 5, value: 60 },
+            Transition { level: 8, value: 100 },
+        ],
+        level,
+    );
+    if cave_chance > 0 && rng.gen_range(0..100) < cave_chance {
+        MapStyle::Caves
+    } else if rng.gen() {
+        MapStyle::Bsp
+    } else {
+        MapStyle::Rooms
+    }
+}
 
-    let mut rooms: Vec<Rect> = vec![];
+/// the passes that build a floor in the given style
+pub fn builder_for<'a>(
+    style: MapStyle,
+    monsters: &'a [MonsterTemplate],
+    prefabs: &'a [Prefab],
+) -> BuilderChain<'a> {
+    match style {
+        MapStyle::Rooms => BuilderChain::new(RandomRooms)
+            .with(Pillars)
+            .with(Doorways)
+            .with(FirstAndLastRoom),
+        MapStyle::Bsp => BuilderChain::new(Bsp)
+            .with(Pillars)
+            .with(Doorways)
+            .with(FirstAndLastRoom),
+        MapStyle::Caves => BuilderChain::new(Caves).with(FarthestApart),
+    }
+    .with(Terrain)
+    .with(Vaults { prefabs, monsters })
+    .with(VoidRifts)
+    .with(Doors)
+    .with(Spawns { monsters })
+}
 
-    for _ in 0..MAX_ROOMS {
-        // random width and height
-        let w = rand::thread_rng().gen_range(ROOM_MIN_SIZE..ROOM_MAX_SIZE + 1);
-        let h = rand::thread_rng().gen_range(ROOM_MIN_SIZE..ROOM_MAX_SIZE + 1);
-        // random position without going out of the boundaries of the map
-        let x = rand::thread_rng().gen_range(0..(map.len() as i32) - w);
-        let y = rand::thread_rng().gen_range(0..(map[0].len() as i32) - h);
+/// generate the floor at the given depth, in whichever style suits it, and
+/// return it with where its stairs down are. Everything but the player is
+/// removed from `objects` first, and the player is moved to where the floor
+/// starts.
+pub fn make_map(
+    width: i32,
+    height: i32,
+    objects: &mut Vec<Object>,
+    rng: &mut GameRng,
+    level: u32,
+    monsters: &[MonsterTemplate],
+    prefabs: &[Prefab],
+) -> (Map, Option<(i32, i32)>) {
+    let style = map_style(level, rng);
+    let data = builder_for(style, monsters, prefabs).build(width, height, level, rng);
+
+    // Player is the first element, remove everything else.
+    objects.truncate(PLAYER + 1);
+    if let Some((x, y)) = data.start {
+        objects[PLAYER].set_pos(x, y);
+    }
+    objects.extend(data.spawns);
 
-        let new_room = Rect::new(x, y, w, h);
+    // create stairs where the way down was put
+    if let Some((x, y)) = data.exit {
+        let mut stairs = Object::new(x, y, '<', "stairs", WHITE, false);
+        stairs.always_visible = true;
+        objects.push(stairs);
+    }
 
-        // run through the other rooms and see if they intersect with this one
-        let failed = rooms
-            .iter()
-            .any(|other_room| new_room.intersects_with(other_room));
-
-        if !failed {
-            // this means there are no intersections, so this room is valid
-
-            // "paint" it to the map's tiles
-            create_room(new_room, &mut map);
-
-            // center coordinates of the new room, will be useful later
-            let (new_x, new_y) = new_room.center();
-
-            if rooms.is_empty() {
-                // this is the first room, where the player starts at
-                objects[0].set_pos(new_x, new_y);
-            } else {
-                
-                // place objects in the other rooms
-                place_objects(new_room, objects, &mut map);
-
-                // center coordinates of the previous room
-                let (prev_x, prev_y) = rooms[rooms.len() - 1].center();
-
-                // toss a coin (random bool value -- either true or false)
-                if rand::random() {
-                    // first move horizontally, then vertically
-                    create_h_tunnel(prev_x, new_x, prev_y, &mut map);
-                    create_v_tunnel(prev_y, new_y, new_x, &mut map);
-                } else {
-                    // first move vertically, then horizontally
-                    create_v_tunnel(prev_y, new_y, prev_x, &mut map);
-                    create_h_tunnel(prev_x, new_x, new_y, &mut map);
+    (data.map, data.exit)
+}
+
+/// how many steps it takes to walk from `start` to each tile of the map, in
+/// 8 directions, through doors and ignoring objects, or None where it can't be reached
+pub fn distances_from(map: &Map, start: (i32, i32)) -> Vec<Vec<Option<u32>>> {
+    let mut distances = vec![vec![None; map[0].len()]; map.len()];
+    distances[start.0 as usize][start.1 as usize] = Some(0);
+    let mut queue = VecDeque::from([start]);
+    while let Some((x, y)) = queue.pop_front() {
+        let distance = distances[x as usize][y as usize].unwrap_or_default();
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let (next_x, next_y) = (x + dx, y + dy);
+                if next_x < 0
+                    || next_y < 0
+                    || next_x >= map.len() as i32
+                    || next_y >= map[0].len() as i32
+                {
+                    continue;
+                }
+                let (nx, ny) = (next_x as usize, next_y as usize);
+                if map[nx][ny].passable() && distances[nx][ny].is_none() {
+                    distances[nx][ny] = Some(distance + 1);
+                    queue.push_back((next_x, next_y));
                 }
             }
-
-            // finally, append the new room to the list
-            rooms.push(new_room);
         }
     }
-    map
+    distances
 }
 
-pub(crate) fn render_map(tcod: &mut Tcod, map: &Map, game: &mut Game) {
-    // go through all tiles, and set their background color
-    for y in 0..map[0].len() {
-        for x in 0..map.len() {
-            let visible = tcod.fov.is_in_fov(x as i32, y as i32);
-            let wall = game.map[x as usize][y as usize].block_sight;
-            let color = match (visible, wall) {
-                // outside of field of view:
-                (false, true) => COLOR_DARK_WALL,
-                (false, false) => COLOR_DARK_GROUND,
-                // inside fov:
-                (true, true) => COLOR_LIGHT_WALL,
-                (true, false) => COLOR_LIGHT_GROUND,
-            };
-            let glyph = match game.map[x as usize][y as usize].blocked {
-                false => '.',
-                true => '#',
-                
-                
-            };
-            
-
-            let explored = &mut game.map[x as usize][y as usize].explored;
-            if visible {
-                // since it's visible, explore it
-                *explored = true;
-            }
-            if *explored {
-                // show explored tiles only (any visible tile is explored already)
-                tcod.con.put_char_ex(x as i32, y as i32, glyph, color, BLACK);
+/// whether every floor tile (and door) of the map can be walked to from `start`
+pub fn is_connected(map: &Map, start: (i32, i32)) -> bool {
+    let distances = distances_from(map, start);
+    map.iter().zip(&distances).all(|(column, distances)| {
+        column
+            .iter()
+            .zip(distances)
+            .all(|(tile, distance)| !tile.passable() || distance.is_some())
+    })
+}
+
+/// Rooms of random sizes scattered over the map, each joined to the one
+/// before it by an L-shaped tunnel.
+pub struct RandomRooms;
+
+impl MapBuilder for RandomRooms {
+    fn build(&self, data: &mut BuildData, rng: &mut GameRng) {
+        let map = &mut data.map;
+        let rooms = &mut data.rooms;
+
+        for _ in 0..MAX_ROOMS {
+            // random width and height
+            let w = rng.gen_range(ROOM_MIN_SIZE..ROOM_MAX_SIZE + 1);
+            let h = rng.gen_range(ROOM_MIN_SIZE..ROOM_MAX_SIZE + 1);
+            // random position without going out of the boundaries of the map
+            let x = rng.gen_range(0..(map.len() as i32) - w);
+            let y = rng.gen_range(0..(map[0].len() as i32) - h);
+
+            let new_room = Rect::new(x, y, w, h);
+
+            // run through the other rooms and see if they intersect with this one
+            let failed = rooms
+                .iter()
+                .any(|other_room| new_room.intersects_with(other_room));
+
+            if !failed {
+                // this means there are no intersections, so this room is valid
+
+                // "paint" it to the map's tiles
+                create_room(new_room, map);
+
+                if let Some(prev_room) = rooms.last() {
+                    // center coordinates of the new and the previous room
+                    let (new_x, new_y) = new_room.center();
+                    let (prev_x, prev_y) = prev_room.center();
+
+                    // toss a coin (random bool value -- either true or false)
+                    if rng.gen() {
+                        // first move horizontally, then vertically
+                        create_h_tunnel(prev_x, new_x, prev_y, map);
+                        create_v_tunnel(prev_y, new_y, new_x, map);
+                    } else {
+                        // first move vertically, then horizontally
+                        create_v_tunnel(prev_y, new_y, prev_x, map);
+                        create_h_tunnel(prev_x, new_x, new_y, map);
+                    }
+                }
+
+                // finally, append the new room to the list
+                rooms.push(new_room);
             }
         }
     }
 }
 
-
 /// A rectangle on the map, used to characterise a room.
-#[derive(Clone, Copy, Debug)]
-pub(crate) struct Rect {
-    x1: i32,
-    y1: i32,
-    x2: i32,
-    y2: i32,
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x1: i32,
+    pub y1: i32,
+    pub x2: i32,
+    pub y2: i32,
 }
 
 impl Rect {
@@ -183,6 +428,11 @@ impl Rect {
         (center_x, center_y)
     }
     
+    /// whether the tile is inside the rectangle (walls included)
+    pub fn contains(&self, (x, y): (i32, i32)) -> bool {
+        (self.x1..=self.x2).contains(&x) && (self.y1..=self.y2).contains(&y)
+    }
+
     pub fn intersects_with(&self, other: &Rect) -> bool {
         // returns true if this rectangle intersects with another one
         (self.x1 <= other.x2)
@@ -192,7 +442,7 @@ impl Rect {
     }
 }
 
-fn create_room(room: Rect, map: &mut Map) {
+pub(crate) fn create_room(room: Rect, map: &mut Map) {
     // go through the tiles in the rectangle and make them passable
     for x in (room.x1 + 1)..room.x2 {
         for y in (room.y1 + 1)..room.y2 {
@@ -201,64 +451,157 @@ fn create_room(room: Rect, map: &mut Map) {
     }
 }
 
-fn create_h_tunnel(x1: i32, x2: i32, y: i32, map: &mut Map) {
+pub(crate) fn create_h_tunnel(x1: i32, x2: i32, y: i32, map: &mut Map) {
     // horizontal tunnel. `min()` and `max()` are used in case `x1 > x2`
     for x in cmp::min(x1, x2)..(cmp::max(x1, x2) + 1) {
         map[x as usize][y as usize] = Tile::empty();
     }
 }
 
-fn create_v_tunnel(y1: i32, y2: i32, x: i32, map: &mut Map) {
+pub(crate) fn create_v_tunnel(y1: i32, y2: i32, x: i32, map: &mut Map) {
     // vertical tunnel
     for y in cmp::min(y1, y2)..(cmp::max(y1, y2) + 1) {
         map[x as usize][y as usize] = Tile::empty();
     }
 }
 
-pub(crate) fn place_objects(room: Rect, objects: &mut Vec<Object>, map: &mut Map) {
-
-    const MAX_ROOM_MONSTERS: i32 = 3;
-
-    // choose random number of monsters
-    let num_monsters = rand::thread_rng().gen_range(0..MAX_ROOM_MONSTERS + 1);
-
-    for _ in 0..num_monsters {
-        
-        // choose random spot for this monster
-        let x = rand::thread_rng().gen_range(room.x1 + 1..room.x2);
-        let y = rand::thread_rng().gen_range(room.y1 + 1..room.y2);
-        if !is_blocked(x, y, map, objects) {
-            let mut monster = if rand::random::<f32>() < 0.8 {
-                // baby spider
-                let mut baby_spider = Object::new(x, y, 's',"Baby Spider", LIGHT_RED, true);
-                baby_spider.fighter = Some(Fighter {
-                    max_hp: 6,
-                    hp: 6,
-                    defense: 0,
-                    power: 2,
-                    magic: 0,
-                    magic_defense: 0,
-                    on_death: DeathCallback::Monster
-                });
-                baby_spider.ai = Some(Ai::Basic);
-                baby_spider
-            } else {
-                // zombie
-                let mut zombie = Object::new(x, y, 'Z', "Zombie", LIGHT_GREEN, true);
-                zombie.fighter = Some(Fighter {
-                    max_hp: 15,
-                    hp: 15,
-                    defense: 1,
-                    power: 4,
-                    magic: 0,
-                    magic_defense: 0,
-                    on_death: DeathCallback::Monster
-                });
-                zombie.ai = Some(Ai::Basic);
-                zombie
-            };
-            monster.alive = true;
-            objects.push(monster);
+/// a value that takes effect from some dungeon level onwards
+pub(crate) struct Transition {
+    pub level: u32,
+    pub value: u32,
+}
+
+/// returns a value that depends on level. the table specifies what
+/// value occurs after each level, default is 0.
+pub(crate) fn from_dungeon_level(table: &[Transition], level: u32) -> u32 {
+    table
+        .iter()
+        .rev()
+        .find(|transition| level >= transition.level)
+        .map_or(0, |transition| transition.value)
+}
+
+pub(crate) fn place_objects(
+    room: Rect,
+    objects: &mut Vec<Object>,
+    map: &mut Map,
+    rng: &mut GameRng,
+    level: u32,
+    monsters: &[MonsterTemplate],
+) {
+    // maximum number of monsters per room
+    let max_monsters = from_dungeon_level(
+        &[
+            Transition { level: 1, value: 2 },
+            Transition { level: 4, value: 3 },
+            Transition { level: 6, value: 5 },
+        ],
+        level,
+    );
+
+    // only the monsters allowed at this depth can appear, each as often as its weight says
+    let candidates: Vec<&MonsterTemplate> = monsters
+        .iter()
+        .filter(|monster| monster.spawns_at(level))
+        .collect();
+    if let Ok(monster_choice) = WeightedIndex::new(candidates.iter().map(|monster| monster.spawn_weight)) {
+        // choose random number of monsters
+        let num_monsters = rng.gen_range(0..max_monsters + 1);
+
+        for _ in 0..num_monsters {
+            // choose random spot for this monster
+            let x = rng.gen_range(room.x1 + 1..room.x2);
+            let y = rng.gen_range(room.y1 + 1..room.y2);
+            if !is_blocked(x, y, map, objects) && !map[x as usize][y as usize].kind.is_hazard() {
+                let template = candidates[monster_choice.sample(rng)];
+                objects.push(template.spawn(x, y));
+            }
         }
     }
-}
\ No newline at end of file
+
+    // maximum number of items per room
+    let max_items = from_dungeon_level(
+        &[
+            Transition { level: 1, value: 1 },
+            Transition { level: 4, value: 2 },
+        ],
+        level,
+    );
+
+    // choose random number of items
+    let num_items = rng.gen_range(0..max_items + 1);
+
+    // item random table
+    let item_chances = [
+        (Item::Heal, 35),
+        (
+            Item::Lightning,
+            from_dungeon_level(&[Transition { level: 4, value: 25 }], level),
+        ),
+        (
+            Item::Fireball,
+            from_dungeon_level(&[Transition { level: 6, value: 25 }], level),
+        ),
+        (
+            Item::Confuse,
+            from_dungeon_level(&[Transition { level: 2, value: 10 }], level),
+        ),
+        (
+            Item::Regeneration,
+            from_dungeon_level(&[Transition { level: 2, value: 15 }], level),
+        ),
+        (
+            Item::Purify,
+            from_dungeon_level(&[Transition { level: 2, value: 15 }], level),
+        ),
+        (
+            Item::Helmet,
+            from_dungeon_level(&[Transition { level: 2, value: 5 }], level),
+        ),
+        (
+            Item::LeatherArmor,
+            from_dungeon_level(&[Transition { level: 3, value: 5 }], level),
+        ),
+        (
+            Item::Sword,
+            from_dungeon_level(&[Transition { level: 4, value: 5 }], level),
+        ),
+        (
+            Item::Shield,
+            from_dungeon_level(&[Transition { level: 6, value: 10 }], level),
+        ),
+        (
+            Item::RingOfSorcery,
+            from_dungeon_level(&[Transition { level: 5, value: 5 }], level),
+        ),
+        (
+            Item::ThrowingKnives,
+            from_dungeon_level(&[Transition { level: 1, value: 10 }], level),
+        ),
+        (
+            Item::Bow,
+            from_dungeon_level(&[Transition { level: 2, value: 5 }], level),
+        ),
+        (
+            Item::Arrows,
+            from_dungeon_level(&[Transition { level: 2, value: 10 }], level),
+        ),
+        (
+            Item::AmuletOfWarding,
+            from_dungeon_level(&[Transition { level: 7, value: 5 }], level),
+        ),
+    ];
+    let item_choice = WeightedIndex::new(item_chances.iter().map(|item| item.1)).unwrap();
+
+    for _ in 0..num_items {
+        // choose random spot for this item
+        let x = rng.gen_range(room.x1 + 1..room.x2);
+        let y = rng.gen_range(room.y1 + 1..room.y2);
+
+        // only place it if the tile is not blocked (or burning)
+        if !is_blocked(x, y, map, objects) && !map[x as usize][y as usize].kind.is_hazard() {
+            let kind = item_chances[item_choice.sample(rng)].0;
+            objects.push(kind.spawn(x, y));
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use tcod::colors::Color;
+
+/// how many messages are kept by default before the oldest are forgotten
+pub const DEFAULT_MAX_MESSAGES: usize = 1000;
+
+/// One entry of the message log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub text: String,
+    pub color: Color,
+    /// the turn it was first said on
+    pub turn: u64,
+    /// how many times in a row it was said
+    pub count: u32,
+}
+
+impl Message {
+    /// the text as it's shown, like "Zombie attacks player for 4 hit points. x3"
+    pub fn display(&self) -> String {
+        if self.count > 1 {
+            format!("{} x{}", self.text, self.count)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Messages {
+    messages: VecDeque<Message>,
+    /// how many are kept at most, the oldest going first
+    max_messages: usize,
+    /// the turn new messages are stamped with
+    turn: u64,
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Messages {
+    pub fn new() -> Self {
+        Self {
+            messages: VecDeque::new(),
+            max_messages: DEFAULT_MAX_MESSAGES,
+            turn: 0,
+        }
+    }
+
+    /// add the new message with its color, stamped with the current turn; saying
+    /// the same thing again only counts it on the last one
+    pub fn add<T: Into<String>>(&mut self, message: T, color: Color) {
+        let text = message.into();
+        if let Some(last) = self.messages.back_mut() {
+            if last.text == text && last.color == color {
+                last.count += 1;
+                return;
+            }
+        }
+        self.messages.push_back(Message {
+            text,
+            color,
+            turn: self.turn,
+            count: 1,
+        });
+        self.forget_oldest();
+    }
+
+    /// Create a `DoubleEndedIterator` over the messages, oldest first
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Message> {
+        self.messages.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// the messages containing `query`, ignoring case, oldest first
+    pub fn search<'a>(&'a self, query: &str) -> impl DoubleEndedIterator<Item = &'a Message> {
+        let query = query.to_lowercase();
+        self.messages
+            .iter()
+            .filter(move |message| message.text.to_lowercase().contains(&query))
+    }
+
+    /// the turn that's being played, for the messages added from now on
+    pub fn set_turn(&mut self, turn: u64) {
+        self.turn = turn;
+    }
+
+    /// keep at most `max_messages` (at least one), forgetting the oldest past that
+    pub fn set_max_messages(&mut self, max_messages: usize) {
+        self.max_messages = max_messages.max(1);
+        self.forget_oldest();
+    }
+
+    fn forget_oldest(&mut self) {
+        while self.messages.len() > self.max_messages {
+            self.messages.pop_front();
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use tcod::colors::Color;
+
+use crate::object::{Ai, DamageKind, DeathCallback, Fighter, Object, OnHit};
+use crate::schedule::{NORMAL_SPEED, TICKS_PER_TURN};
+
+/// where the monster definitions live, relative to the game's directory
+pub const MONSTERS_PATH: &str = "assets/monsters.ron";
+
+/// Everything needed to spawn one kind of monster, as written in `assets/monsters.ron`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MonsterTemplate {
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+    /// what the look mode says about it
+    #[serde(default)]
+    pub description: String,
+    pub max_hp: i32,
+    pub defense: i32,
+    pub power: i32,
+    #[serde(default)]
+    pub magic: i32,
+    #[serde(default)]
+    pub magic_defense: i32,
+    pub ai: Ai,
+    /// whether its attacks hit with `power` (Physical) or `magic` (Magical)
+    #[serde(default)]
+    pub attack: DamageKind,
+    /// a status effect its hits may inflict
+    #[serde(default)]
+    pub on_hit: Option<OnHit>,
+    /// how quickly it acts; 100 is once per turn, like the player
+    #[serde(default = "normal_speed")]
+    pub speed: i32,
+    /// experience the player earns for killing it
+    #[serde(default)]
+    pub xp: i32,
+    /// whether it opens closed doors on its way to the player
+    #[serde(default)]
+    pub opens_doors: bool,
+    /// how likely this monster is to be picked, relative to the others allowed at the same depth
+    pub spawn_weight: u32,
+    /// the first dungeon level this monster can appear on
+    pub min_depth: u32,
+    /// the last dungeon level this monster can appear on, if there is one
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+}
+
+fn normal_speed() -> i32 {
+    NORMAL_SPEED
+}
+
+impl MonsterTemplate {
+    /// whether this monster may be spawned on the given dungeon level
+    pub fn spawns_at(&self, level: u32) -> bool {
+        level >= self.min_depth && self.max_depth.is_none_or(|max| level <= max)
+    }
+
+    /// create a live monster from this template at (x, y)
+    pub fn spawn(&self, x: i32, y: i32) -> Object {
+        let mut monster = Object::new(x, y, self.glyph, &self.name, self.color, true);
+        monster.fighter = Some(Fighter {
+            max_hp: self.max_hp,
+            hp: self.max_hp,
+            defense: self.defense,
+            power: self.power,
+            magic: self.magic,
+            magic_defense: self.magic_defense,
+            attack: self.attack,
+            on_hit: self.on_hit,
+            speed: self.speed,
+            xp: self.xp,
+            on_death: DeathCallback::Monster,
+        });
+        monster.ai = Some(self.ai.clone());
+        monster.opens_doors = self.opens_doors;
+        monster.alive = true;
+        monster
+    }
+
+    /// check the values that can be parsed fine but make no sense
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("a monster has an empty name".into());
+        }
+        let problem = if self.max_hp < 1 {
+            format!("max_hp is {}, it must be at least 1", self.max_hp)
+        } else if self.defense < 0
+            || self.power < 0
+            || self.magic < 0
+            || self.magic_defense < 0
+            || self.xp < 0
+        {
+            "defense, power, magic, magic_defense and xp can't be negative".into()
+        } else if self.speed < TICKS_PER_TURN || self.speed % TICKS_PER_TURN != 0 {
+            format!(
+                "speed is {}, it must be a positive multiple of {}",
+                self.speed, TICKS_PER_TURN
+            )
+        } else if self.spawn_weight == 0 {
+            "spawn_weight is 0, so it would never appear".into()
+        } else if self.min_depth < 1 {
+            "min_depth must be at least 1 (the first dungeon level)".into()
+        } else if self.max_depth.is_some_and(|max| max < self.min_depth) {
+            format!(
+                "max_depth ({}) is shallower than min_depth ({})",
+                self.max_depth.unwrap_or_default(),
+                self.min_depth
+            )
+        } else if matches!(self.ai, Ai::Ranged { range } if range < 2) {
+            "a Ranged monster's range must be at least 2, or it could only shoot point-blank".into()
+        } else if self.attack == DamageKind::Magical && self.magic == 0 {
+            "its attacks are Magical but its magic is 0, so they'd never hurt".into()
+        } else if let Some(on_hit) = self.on_hit {
+            if on_hit.chance == 0 || on_hit.chance > 100 {
+                format!("on_hit chance is {}, it must be between 1 and 100", on_hit.chance)
+            } else if on_hit.effect.turns < 1 {
+                "on_hit effect must last at least 1 turn".into()
+            } else {
+                return Ok(());
+            }
+        } else {
+            return Ok(());
+        };
+        Err(format!("monster {:?}: {}", self.name, problem))
+    }
+}
+
+/// parse and validate monster definitions written in RON
+pub fn parse_monsters(source: &str) -> Result<Vec<MonsterTemplate>, String> {
+    let monsters: Vec<MonsterTemplate> = ron::from_str(source).map_err(|e| e.to_string())?;
+    if monsters.is_empty() {
+        return Err("no monsters are defined".into());
+    }
+
+    let mut names = HashSet::new();
+    for monster in &monsters {
+        monster.validate()?;
+        if !names.insert(monster.name.as_str()) {
+            return Err(format!("monster {:?} is defined more than once", monster.name));
+        }
+    }
+    Ok(monsters)
+}
+
+/// read the monster definitions from a file, with errors that point at the file
+pub fn load_monsters(path: impl AsRef<Path>) -> Result<Vec<MonsterTemplate>, Box<dyn Error>> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let monsters =
+        parse_monsters(&source).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(monsters)
+}
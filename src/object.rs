@@ -1,12 +1,21 @@
 use rand::Rng;
-use tcod::{colors::*, Console, BackgroundFlag, input::Mouse, Map as FovMap};
+use serde::{Deserialize, Serialize};
+use tcod::colors::*;
 use std::cmp;
 
-use crate::{Game, map::Map, PLAYER, Tcod};
+use crate::equipment::Equipment;
+use crate::game::{Game, PLAYER};
+use crate::item::Item;
+use crate::map::{Map, TileKind};
+use crate::path::{find_path, PATH_BUDGET};
+use crate::ranged::{clear_shot, monster_shoot, KEEP_DISTANCE};
+use crate::schedule::{Action, ACTION_ENERGY};
+use crate::status::{lose_stunned_turn, StatusEffect, StatusKind};
+use crate::void::{void_rift_death, VOID_POWER_BONUS};
 
 /// This is a generic object: the player, a monster, an item, the stairs...
 /// It's always represented by a character on screen.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Object {
    pub x: i32,
    pub  y: i32,
@@ -15,13 +24,27 @@ pub struct Object {
    pub name: String,  
    pub blocks: bool,  
    pub alive: bool,  
+   pub level: i32,
    pub fighter: Option<Fighter>,  
    pub ai: Option<Ai>,  
+   /// status effects currently active on it
+   pub effects: Vec<StatusEffect>,
+   /// stored up towards its next action, see `schedule`
+   pub energy: i32,
+   pub item: Option<Item>,
+   /// what it gives whoever wears it, if it can be worn
+   pub equipment: Option<Equipment>,
+   /// how many there are, for items that stack (ammunition)
+   pub count: u32,
+   /// drawn even outside the FOV once its tile has been explored (e.g. stairs)
+   pub always_visible: bool,
+   /// whether it opens closed doors in its way (the player always can)
+   pub opens_doors: bool,
 }
 
-pub(crate) fn is_blocked(x: i32, y: i32, map: &Map, objects: &[Object]) -> bool {
+pub fn is_blocked(x: i32, y: i32, map: &Map, objects: &[Object]) -> bool {
     // first test the map tile
-    if map[x as usize][y as usize].blocked {
+    if map[x as usize][y as usize].blocked() {
         return true;
     }
     // now check for any blocking objects
@@ -34,41 +57,26 @@ impl Object {
     /// create a new object
     pub fn new(x: i32, y: i32, char: char, name: &str, color: Color, blocks: bool) -> Self {
         Object {
-            x: x,
-            y: y,
-            char: char,
-            color: color,
+            x,
+            y,
+            char,
+            color,
             name: name.into(),
-            blocks: blocks,
+            blocks,
             alive: false,
+            level: 1,
             fighter: None,  
             ai: None,  
+            effects: vec![],
+            energy: 0,
+            item: None,
+            equipment: None,
+            count: 1,
+            always_visible: false,
+            opens_doors: false,
         }
     }
 
-    
-    /// move or attack by the given destination
-    pub fn player_move_or_attack(&mut self, dx: i32, dy: i32, game: &mut Game, objects: &mut [Object]) {
-        let x = self.x + dx;
-        let y = self.y + dy;
-// try to find an attackable object there
-let target_id = objects
-    .iter()
-    .position(|object| object.fighter.is_some() && object.pos() == (x, y));
-
-        if let Some(target_id) = target_id {
-            let (player, target) = mut_two(PLAYER, target_id, objects);
-            player.attack(target, game);
-        } else {
-            move_by(PLAYER, dx, dy, &game.map, objects);
-        }
-    }
-
-    /// set the color and then draw the character that represents this object at its position
-    pub fn draw(&self, con: &mut dyn Console) {
-        con.set_default_foreground(self.color);
-        con.put_char(self.x, self.y, self.char, BackgroundFlag::None);
-    }
 
     /// set the position of this object
     pub fn set_pos(&mut self, x: i32, y: i32) {
@@ -89,7 +97,102 @@ let target_id = objects
         ((dx.pow(2) + dy.pow(2)) as f32).sqrt()
     }
 
-    pub fn take_damage(&mut self, damage: i32, game: &mut Game) {
+    /// return the distance to some coordinates
+    pub fn distance(&self, x: i32, y: i32) -> f32 {
+        (((x - self.x).pow(2) + (y - self.y).pow(2)) as f32).sqrt()
+    }
+
+    /// heal by the given amount, without going over the maximum
+    pub fn heal(&mut self, amount: i32, game: &Game) {
+        let max_hp = self.max_hp(game);
+        if let Some(ref mut fighter) = self.fighter {
+            fighter.hp += amount;
+            if fighter.hp > max_hp {
+                fighter.hp = max_hp;
+            }
+        }
+    }
+
+    /// a rough idea of how hurt it is, the way it looks from outside
+    pub fn health_estimate(&self, game: &Game) -> Option<&'static str> {
+        let fighter = self.fighter?;
+        let health = fighter.hp as f32 / self.max_hp(game) as f32;
+        let estimate = if health >= 1.0 {
+            "unhurt"
+        } else if health >= 0.75 {
+            "barely scratched"
+        } else if health >= 0.5 {
+            "wounded"
+        } else if health >= 0.25 {
+            "badly wounded"
+        } else {
+            "almost dead"
+        };
+        Some(estimate)
+    }
+
+    /// the equipment this object has on; only the player wears anything,
+    /// and theirs is kept in the inventory
+    pub fn get_all_equipped(&self, game: &Game) -> Vec<Equipment> {
+        if self.fighter.is_some_and(|f| f.on_death == DeathCallback::Player) {
+            game.inventory
+                .iter()
+                .filter_map(|item| item.equipment)
+                .filter(|equipment| equipment.equipped)
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
+    /// its power, counting what it has equipped
+    pub fn power(&self, game: &Game) -> i32 {
+        let base_power = self.fighter.map_or(0, |f| f.power);
+        let bonus: i32 = self.get_all_equipped(game).iter().map(|e| e.power_bonus).sum();
+        base_power + bonus
+    }
+
+    /// its defense, counting what it has equipped
+    pub fn defense(&self, game: &Game) -> i32 {
+        let base_defense = self.fighter.map_or(0, |f| f.defense);
+        let bonus: i32 = self.get_all_equipped(game).iter().map(|e| e.defense_bonus).sum();
+        base_defense + bonus
+    }
+
+    /// its magic, counting what it has equipped
+    pub fn magic(&self, game: &Game) -> i32 {
+        let base_magic = self.fighter.map_or(0, |f| f.magic);
+        let bonus: i32 = self.get_all_equipped(game).iter().map(|e| e.magic_bonus).sum();
+        base_magic + bonus
+    }
+
+    /// its magic defense, counting what it has equipped
+    pub fn magic_defense(&self, game: &Game) -> i32 {
+        let base_magic_defense = self.fighter.map_or(0, |f| f.magic_defense);
+        let bonus: i32 = self
+            .get_all_equipped(game)
+            .iter()
+            .map(|e| e.magic_defense_bonus)
+            .sum();
+        base_magic_defense + bonus
+    }
+
+    /// its maximum HP, counting what it has equipped
+    pub fn max_hp(&self, game: &Game) -> i32 {
+        let base_max_hp = self.fighter.map_or(0, |f| f.max_hp);
+        let bonus: i32 = self.get_all_equipped(game).iter().map(|e| e.max_hp_bonus).sum();
+        base_max_hp + bonus
+    }
+
+    /// add experience points, if this is something that can gain them
+    pub fn gain_xp(&mut self, xp: i32) {
+        if let Some(fighter) = self.fighter.as_mut() {
+            fighter.xp += xp;
+        }
+    }
+
+    /// apply damage, returning the XP it was worth if this killed it
+    pub fn take_damage(&mut self, damage: i32, game: &mut Game) -> Option<i32> {
         // apply damage if possible
         if let Some(fighter) = self.fighter.as_mut() {
             if damage > 0 {
@@ -101,24 +204,60 @@ let target_id = objects
         if let Some(fighter) = self.fighter {
             if fighter.hp <= 0 {
                 self.alive = false;
+                self.effects.clear();
                 fighter.on_death.callback(self, game);
+                return Some(fighter.xp);
             }
         }
+        None
     }
 
-    pub fn attack(&mut self, target: &mut Object, game: &mut Game) {
+    /// how much damage a hit of the given kind from this object does to `target`
+    pub fn damage_against(&self, target: &Object, kind: DamageKind, game: &Game) -> i32 {
         // a simple(ish) formula for attack damage
-        let damage = self.fighter.map_or(0, |f| f.power) - target.fighter.map_or(0, |f| f.defense) / 4;
+        let damage = match kind {
+            DamageKind::Physical => self.power(game) - target.defense(game) / 4,
+            DamageKind::Magical => self.magic(game) - target.magic_defense(game) / 4,
+        };
+        // the void lends its strength to the monsters standing in it
+        let is_monster = self.fighter.is_some_and(|f| f.on_death == DeathCallback::Monster);
+        if is_monster && game.map[self.x as usize][self.y as usize].kind == TileKind::VoidFloor {
+            damage + VOID_POWER_BONUS
+        } else {
+            damage
+        }
+    }
+
+    /// hit `target` with this object's usual kind of attack
+    pub fn attack(&mut self, target: &mut Object, game: &mut Game) {
+        let kind = self.fighter.map_or(DamageKind::Physical, |f| f.attack);
+        let damage = self.damage_against(target, kind, game);
         if damage > 0 {
             // make the target take some damage
-            game.messages.add(
-                format!(
+            let text = match kind {
+                DamageKind::Physical => format!(
                     "{} attacks {} for {} hit points.",
                     self.name, target.name, damage
                 ),
-                WHITE,
-            );
-            target.take_damage(damage, game);
+                DamageKind::Magical => format!(
+                    "{} blasts {} with dark magic for {} hit points.",
+                    self.name, target.name, damage
+                ),
+            };
+            game.messages.add(text, kind.color());
+            if let Some(xp) = target.take_damage(damage, game) {
+                // yield experience to the attacker
+                self.gain_xp(xp);
+            } else if let Some(on_hit) = self.fighter.and_then(|f| f.on_hit) {
+                // a hit that didn't kill may leave something behind (poison, stun...)
+                if game.rng.gen_range(0..100) < on_hit.chance {
+                    game.messages.add(
+                        format!("{} is {}!", target.name, on_hit.effect.kind.adjective()),
+                        on_hit.effect.kind.color(),
+                    );
+                    target.add_effect(on_hit.effect);
+                }
+            }
         } else {
             game.messages.add(
                 format!(
@@ -131,45 +270,192 @@ let target_id = objects
     }
 }
 
+/// move or attack by the given destination, returning which one it was
+pub fn player_move_or_attack(dx: i32, dy: i32, game: &mut Game, objects: &mut [Object]) -> Action {
+    let x = objects[PLAYER].x + dx;
+    let y = objects[PLAYER].y + dy;
+
+    // try to find an attackable object there, other than the player
+    let target_id = (0..objects.len()).find(|&id| {
+        id != PLAYER && objects[id].fighter.is_some() && objects[id].pos() == (x, y)
+    });
+
+    if let Some(target_id) = target_id {
+        let (player, target) = mut_two(PLAYER, target_id, objects);
+        player.attack(target, game);
+        Action::Attack
+    } else if game.map[x as usize][y as usize].is_closed_door() {
+        // bumping into a door opens it
+        game.set_door(x, y, true);
+        game.messages.add("You open the door.", WHITE);
+        Action::Door
+    } else {
+        move_by(PLAYER, dx, dy, game, objects);
+        Action::Move
+    }
+}
+
 /// move by the given amount, if the destination is not blocked
-pub fn move_by(id: usize, dx: i32, dy: i32, map: &Map, objects: &mut [Object]) {
+pub fn move_by(id: usize, dx: i32, dy: i32, game: &mut Game, objects: &mut [Object]) {
     let (x, y) = objects[id].pos();
-    if !is_blocked(x + dx, y + dy, map, objects) {
+    if !is_blocked(x + dx, y + dy, &game.map, objects) {
         objects[id].set_pos(x + dx, y + dy);
+        enter_tile(id, game, objects);
     }
 }
 
-pub fn ai_take_turn(monster_id: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object]) {
+/// what the ground does to whoever just stepped onto it: slow ground takes
+/// longer to cross, and some of it hurts
+fn enter_tile(id: usize, game: &mut Game, objects: &mut [Object]) {
+    let (x, y) = objects[id].pos();
+    let kind = game.map[x as usize][y as usize].kind;
+    // the step was paid for as a normal one, slow ground takes the rest
+    objects[id].energy -= kind.move_cost() - ACTION_ENERGY;
+
+    let damage = kind.contact_damage();
+    if damage == 0 || objects[id].fighter.is_none() {
+        return;
+    }
+    if id == PLAYER || game.fov.is_in_fov(x, y) {
+        game.messages.add(
+            format!("{} {} for {} hit points.", objects[id].name, kind.contact_verb(), damage),
+            kind.light_color(),
+        );
+    }
+    objects[id].take_damage(damage, game);
+}
+
+/// let the monster act, returning what it did
+pub fn ai_take_turn(monster_id: usize, game: &mut Game, objects: &mut [Object]) -> Action {
+    use Ai::*;
+    // status effects get the first say over what the monster does
+    if lose_stunned_turn(monster_id, game, objects) {
+        return Action::Wait;
+    }
+    if objects[monster_id].has_effect(StatusKind::Confusion) {
+        stumble(monster_id, game, objects);
+        return Action::Move;
+    }
+
+    if let Some(ai) = objects[monster_id].ai.take() {
+        let (new_ai, action) = match ai {
+            Basic => ai_basic(monster_id, game, objects),
+            Ranged { range } => ai_ranged(monster_id, range, game, objects),
+        };
+        // the monster may have died during its turn, which clears its AI for good
+        if objects[monster_id].alive {
+            objects[monster_id].ai = Some(new_ai);
+        }
+        action
+    } else {
+        Action::Wait
+    }
+}
+
+fn ai_basic(monster_id: usize, game: &mut Game, objects: &mut [Object]) -> (Ai, Action) {
     // a basic monster takes its turn. If you can see it, it can see you
     let (monster_x, monster_y) = objects[monster_id].pos();
-    if tcod.fov.is_in_fov(monster_x, monster_y) {
+    let action = if game.fov.is_in_fov(monster_x, monster_y) {
         if objects[monster_id].distance_to(&objects[PLAYER]) >= 2.0 {
             // move towards player if far away
             let (player_x, player_y) = objects[PLAYER].pos();
-            move_towards(monster_id, player_x, player_y, &game.map, objects);
-        } else if objects[PLAYER].fighter.map_or(false, |f| f.hp > 0) {
+            move_towards(monster_id, player_x, player_y, game, objects)
+        } else if objects[PLAYER].fighter.is_some_and(|f| f.hp > 0) {
             // close enough, attack! (if the player is still alive.)
-            let player_id = PLAYER;
-            {
-                // close enough, attack! (if the player is still alive.)
-                let (monster, player) = mut_two(monster_id, PLAYER, objects);
-                monster.attack(player, game);
-            }
+            let (monster, player) = mut_two(monster_id, PLAYER, objects);
+            monster.attack(player, game);
+            Action::Attack
         } else {
             // just move in a random direction
-            move_by(
-                monster_id,
-                rand::thread_rng().gen_range(-1..1),
-                rand::thread_rng().gen_range(-1..1),
-                &game.map,
-                objects,
-            );
+            let (dx, dy) = (game.rng.gen_range(-1..1), game.rng.gen_range(-1..1));
+            move_by(monster_id, dx, dy, game, objects);
+            Action::Move
         }
+    } else {
+        Action::Wait
+    };
+    (Ai::Basic, action)
+}
+
+fn ai_ranged(monster_id: usize, range: i32, game: &mut Game, objects: &mut [Object]) -> (Ai, Action) {
+    // a ranged monster keeps its distance and shoots when it has a clear shot
+    let monster_pos = objects[monster_id].pos();
+    let player_pos = objects[PLAYER].pos();
+    let player_alive = objects[PLAYER].fighter.is_some_and(|f| f.hp > 0);
+    let action = if !game.fov.is_in_fov(monster_pos.0, monster_pos.1) || !player_alive {
+        Action::Wait
+    } else {
+        let distance = objects[monster_id].distance_to(&objects[PLAYER]);
+        let clear = clear_shot(monster_pos, player_pos, &game.map, objects);
+        if distance < KEEP_DISTANCE && step_away(monster_id, player_pos, game, objects) {
+            Action::Move
+        } else if distance < 2.0 {
+            // cornered: fight back up close
+            let (monster, player) = mut_two(monster_id, PLAYER, objects);
+            monster.attack(player, game);
+            Action::Attack
+        } else if distance <= range as f32 && clear {
+            monster_shoot(monster_id, player_pos, game, objects);
+            Action::Attack
+        } else {
+            move_towards(monster_id, player_pos.0, player_pos.1, game, objects)
+        }
+    };
+    (Ai::Ranged { range }, action)
+}
+
+/// Step to whichever free tile next to it is furthest from `from`, if any is
+/// further than where it is now. Returns whether it moved.
+fn step_away(id: usize, from: (i32, i32), game: &mut Game, objects: &mut [Object]) -> bool {
+    let (x, y) = objects[id].pos();
+    let distance = |(x, y): (i32, i32)| (x - from.0).pow(2) + (y - from.1).pow(2);
+    let best = (-1..=1)
+        .flat_map(|dx| (-1..=1).map(move |dy| (dx, dy)))
+        .filter(|&(dx, dy)| {
+            let tile = game.map[(x + dx) as usize][(y + dy) as usize];
+            !is_blocked(x + dx, y + dy, &game.map, objects) && !tile.kind.is_hazard()
+        })
+        .max_by_key(|&(dx, dy)| distance((x + dx, y + dy)));
+    match best {
+        Some((dx, dy)) if distance((x + dx, y + dy)) > distance((x, y)) => {
+            move_by(id, dx, dy, game, objects);
+            true
+        }
+        _ => false,
     }
 }
 
-/// move towards the target
-pub fn move_towards(id: usize, target_x: i32, target_y: i32, map: &Map, objects: &mut [Object]) {
+/// move in a random direction (what confused creatures do instead of acting)
+pub fn stumble(id: usize, game: &mut Game, objects: &mut [Object]) {
+    let (dx, dy) = (game.rng.gen_range(-1..=1), game.rng.gen_range(-1..=1));
+    move_by(id, dx, dy, game, objects);
+}
+
+/// Move towards the target, walking around walls and other monsters when there's
+/// a way. Returns whether it moved, or opened a door in its way instead, which
+/// only those that open doors do.
+pub fn move_towards(
+    id: usize,
+    target_x: i32,
+    target_y: i32,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> Action {
+    let from = objects[id].pos();
+    let opens_doors = objects[id].opens_doors;
+    let path = find_path(from, (target_x, target_y), &game.map, objects, opens_doors, PATH_BUDGET);
+    if let Some(path) = path {
+        if let Some(&(next_x, next_y)) = path.first() {
+            if game.map[next_x as usize][next_y as usize].is_closed_door() {
+                game.set_door(next_x, next_y, true);
+                return Action::Door;
+            }
+            move_by(id, next_x - from.0, next_y - from.1, game, objects);
+        }
+        return Action::Move;
+    }
+
+    // no path (or too far to look for one): just step straight at the target
     // vector from this object to the target, and distance
     let dx = target_x - objects[id].x;
     let dy = target_y - objects[id].y;
@@ -179,25 +465,19 @@ pub fn move_towards(id: usize, target_x: i32, target_y: i32, map: &Map, objects:
     // convert to integer so the movement is restricted to the map grid
     let dx = (dx as f32 / distance).round() as i32;
     let dy = (dy as f32 / distance).round() as i32;
-    move_by(id, dx, dy, map, objects);
+    move_by(id, dx, dy, game, objects);
+    Action::Move
 }
 
-/// return a string with the names of all objects under the mouse
-pub fn get_names_under_mouse(mouse: Mouse, objects: &[Object], fov_map: &FovMap) -> String {
-    let (x, y) = (mouse.cx as i32, mouse.cy as i32);
-
-    // create a list with the names of all objects at the mouse's coordinates and in FOV
-    let names = objects
-        .iter()
-        .filter(|obj| obj.pos() == (x, y) && fov_map.is_in_fov(obj.x, obj.y))
-        .map(|obj| obj.name.clone())
-        .collect::<Vec<_>>();
-
-    names.join(", ") // join the names, separated by commas
+/// A status effect a fighter's hits inflict `chance` percent of the time.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OnHit {
+    pub effect: StatusEffect,
+    pub chance: u32,
 }
 
 // combat-related properties and methods (monster, player, NPC).
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Fighter {
     /// maximum hit points (defines the monster's maximum possible health)
     pub max_hp: i32,
@@ -211,13 +491,47 @@ pub struct Fighter {
     pub magic: i32,
     /// magic defense points (defines how much magic damage the monster can absorb from a magical hit)
     pub magic_defense: i32,
+    /// what kind of damage its attacks do
+    #[serde(default)]
+    pub attack: DamageKind,
+    /// an effect its hits may leave on the target
+    #[serde(default)]
+    pub on_hit: Option<OnHit>,
+    /// how quickly it gets to act, `NORMAL_SPEED` being once per turn
+    pub speed: i32,
+    /// experience points (for monsters, how much killing them is worth; for the player, how much was earned)
+    pub xp: i32,
     /// on_death function (called when the monster dies)
     pub on_death: DeathCallback,  
 }
 
+/// The two channels damage can come through: physical hits are reduced by
+/// `defense`, magical ones by `magic_defense`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum DamageKind {
+    #[default]
+    Physical,
+    Magical,
+}
+
+impl DamageKind {
+    /// the color of messages about this kind of damage
+    pub fn color(self) -> Color {
+        match self {
+            DamageKind::Physical => WHITE,
+            DamageKind::Magical => LIGHT_MAGENTA,
+        }
+    }
+}
+
+/// damage done by a spell with the given base power, cast by `caster` at `target`
+pub fn spell_damage(base: i32, caster: &Object, target: &Object, game: &Game) -> i32 {
+    base + caster.magic(game) - target.magic_defense(game) / 4
+}
+
 /// Mutably borrow two *separate* elements from the given slice.
 /// Panics when the indexes are equal or out of bounds.
-fn mut_two<T>(first_index: usize, second_index: usize, items: &mut [T]) -> (&mut T, &mut T) {
+pub(crate) fn mut_two<T>(first_index: usize, second_index: usize, items: &mut [T]) -> (&mut T, &mut T) {
     assert!(first_index != second_index);
     let split_at_index = cmp::max(first_index, second_index);
     let (first_slice, second_slice) = items.split_at_mut(split_at_index);
@@ -228,15 +542,18 @@ fn mut_two<T>(first_index: usize, second_index: usize, items: &mut [T]) -> (&mut
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Ai {
     Basic,
+    /// shoots from up to `range` tiles away, and backs off when the player gets close
+    Ranged { range: i32 },
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum DeathCallback {
     Player,
     Monster,
+    VoidRift,
 }
 
 impl DeathCallback {
@@ -245,6 +562,7 @@ impl DeathCallback {
         let callback: fn(&mut Object, &mut Game) = match self {
             Player => player_death,
             Monster => monster_death,
+            VoidRift => void_rift_death,
         };
         callback(object, game);
     }
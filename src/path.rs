@@ -0,0 +1,124 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::map::Map;
+use crate::object::Object;
+use crate::schedule::ACTION_ENERGY;
+
+/// how many tiles A* may look at before giving up
+pub const PATH_BUDGET: usize = 500;
+
+// cost of a straight step; diagonals cost a bit more so paths don't zig-zag
+const STEP_COST: i32 = 10;
+const DIAGONAL_COST: i32 = 11;
+// extra cost of walking through a tile another creature is standing on.
+// they'll probably have moved by then, so it's avoided rather than forbidden
+const OCCUPIED_COST: i32 = 50;
+// extra cost of stepping on a tile that hurts (lava...), so it's only done
+// when there's no other way, or the way around is very long
+const HAZARD_COST: i32 = 100;
+
+/// Find a path from `from` to `to` with A*, walking in 8 directions.
+/// Walls can't be crossed, and neither can closed doors unless `opens_doors`;
+/// slow ground costs more to cross, and hazards and tiles with blocking
+/// objects on them are only avoided.
+/// Returns the tiles to walk through (not including `from`, ending at `to`),
+/// or None if there is no path or it couldn't be found within `budget` tiles.
+pub fn find_path(
+    from: (i32, i32),
+    to: (i32, i32),
+    map: &Map,
+    objects: &[Object],
+    opens_doors: bool,
+    budget: usize,
+) -> Option<Vec<(i32, i32)>> {
+    let width = map.len() as i32;
+    let height = map[0].len() as i32;
+    let index = |(x, y): (i32, i32)| (x * height + y) as usize;
+    let in_bounds = |(x, y): (i32, i32)| x >= 0 && y >= 0 && x < width && y < height;
+    if !in_bounds(from) || !in_bounds(to) {
+        return None;
+    }
+    if from == to {
+        return Some(vec![]);
+    }
+
+    // remember which tiles have somebody standing on them, for the extra cost
+    let mut occupied = vec![false; (width * height) as usize];
+    for object in objects {
+        if object.blocks && in_bounds(object.pos()) {
+            occupied[index(object.pos())] = true;
+        }
+    }
+
+    // guess of the remaining cost, never more than the real one
+    let heuristic = |(x, y): (i32, i32)| STEP_COST * (x - to.0).abs().max((y - to.1).abs());
+
+    let mut cost_so_far = vec![i32::MAX; (width * height) as usize];
+    let mut came_from = vec![None; (width * height) as usize];
+    let mut open = BinaryHeap::new();
+    cost_so_far[index(from)] = 0;
+    open.push(Reverse((heuristic(from), 0, from)));
+
+    let mut expanded = 0;
+    while let Some(Reverse((_, cost, current))) = open.pop() {
+        if current == to {
+            // walk back from the goal to build the path
+            let mut path = vec![current];
+            let mut tile = current;
+            while let Some(previous) = came_from[index(tile)] {
+                if previous == from {
+                    break;
+                }
+                path.push(previous);
+                tile = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if cost > cost_so_far[index(current)] {
+            // a cheaper way here was already handled
+            continue;
+        }
+        expanded += 1;
+        if expanded > budget {
+            return None;
+        }
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if (dx, dy) == (0, 0) {
+                    continue;
+                }
+                let next = (current.0 + dx, current.1 + dy);
+                if !in_bounds(next) {
+                    continue;
+                }
+                let tile = &map[next.0 as usize][next.1 as usize];
+                if tile.blocked() && !(opens_doors && tile.is_closed_door()) {
+                    continue;
+                }
+                let mut step = if dx != 0 && dy != 0 {
+                    DIAGONAL_COST
+                } else {
+                    STEP_COST
+                };
+                // slow ground costs as much more as it takes longer to cross
+                step = step * tile.kind.move_cost() / ACTION_ENERGY;
+                if tile.kind.is_hazard() {
+                    step += HAZARD_COST;
+                }
+                if next != to && occupied[index(next)] {
+                    step += OCCUPIED_COST;
+                }
+                let next_cost = cost + step;
+                if next_cost < cost_so_far[index(next)] {
+                    cost_so_far[index(next)] = next_cost;
+                    came_from[index(next)] = Some(current);
+                    open.push(Reverse((next_cost + heuristic(next), next_cost, next)));
+                }
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,325 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::builder::{BuildData, MapBuilder};
+use crate::game::GameRng;
+use crate::item::Item;
+use crate::map::{Map, Rect, Tile, TileKind};
+use crate::monsters::MonsterTemplate;
+
+/// where the prefab definitions live, relative to the game's directory
+pub const PREFABS_PATH: &str = "assets/prefabs.ron";
+
+// how many random spots are tried for each prefab before giving up on it
+const PLACEMENT_TRIES: u32 = 200;
+
+/// What a character in a prefab's layout stands for.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub enum Legend {
+    Wall,
+    Floor,
+    /// a way in: floor that a corridor is dug to from the rest of the level
+    Entrance,
+    /// a monster, by its name in `assets/monsters.ron`, standing on floor
+    Monster(String),
+    /// an item lying on floor
+    Item(Item),
+    /// any other kind of tile: Water, Lava, Rubble, VoidFloor, Chasm...
+    Tile(TileKind),
+}
+
+/// A hand-made piece of a level, as written in `assets/prefabs.ron`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Prefab {
+    pub name: String,
+    /// rows of characters, all as wide as each other; see `legend_for`
+    pub layout: Vec<String>,
+    /// what the characters other than the usual ones stand for
+    #[serde(default)]
+    pub legend: HashMap<char, Legend>,
+    /// the percent chance of it being put on a level it's allowed on
+    pub chance: u32,
+    /// the first dungeon level it can appear on
+    pub min_depth: u32,
+    /// the last dungeon level it can appear on, if there is one
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+}
+
+impl Prefab {
+    /// whether this prefab may be put on the given dungeon level
+    pub fn spawns_at(&self, level: u32) -> bool {
+        level >= self.min_depth && self.max_depth.is_none_or(|max| level <= max)
+    }
+
+    pub fn width(&self) -> i32 {
+        self.layout.first().map_or(0, |row| row.chars().count() as i32)
+    }
+
+    pub fn height(&self) -> i32 {
+        self.layout.len() as i32
+    }
+
+    /// What a character of the layout stands for. `#` is wall, `.` floor and
+    /// `+` an entrance unless the legend says otherwise; a space leaves the
+    /// level as it was, so prefabs don't have to be rectangles.
+    pub fn legend_for(&self, glyph: char) -> Option<Legend> {
+        if let Some(legend) = self.legend.get(&glyph) {
+            return Some(legend.clone());
+        }
+        match glyph {
+            '#' => Some(Legend::Wall),
+            '.' => Some(Legend::Floor),
+            '+' => Some(Legend::Entrance),
+            _ => None,
+        }
+    }
+
+    /// every character of the layout with its position inside the prefab
+    fn glyphs(&self) -> impl Iterator<Item = ((i32, i32), char)> + '_ {
+        self.layout.iter().enumerate().flat_map(|(y, row)| {
+            row.chars()
+                .enumerate()
+                .map(move |(x, glyph)| ((x as i32, y as i32), glyph))
+        })
+    }
+
+    /// check the values that can be parsed fine but make no sense
+    fn validate(&self, monsters: &[MonsterTemplate]) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("a prefab has an empty name".into());
+        }
+        let (width, height) = (self.width(), self.height());
+        let problem = if width < 3 || height < 3 {
+            "its layout must be at least 3 by 3".to_string()
+        } else if self.layout.iter().any(|row| row.chars().count() as i32 != width) {
+            "every row of its layout must be as wide".into()
+        } else if self.chance == 0 || self.chance > 100 {
+            format!("chance is {}, it must be between 1 and 100", self.chance)
+        } else if self.min_depth < 1 {
+            "min_depth must be at least 1 (the first dungeon level)".into()
+        } else if self.max_depth.is_some_and(|max| max < self.min_depth) {
+            format!(
+                "max_depth ({}) is shallower than min_depth ({})",
+                self.max_depth.unwrap_or_default(),
+                self.min_depth
+            )
+        } else if let Some(problem) = self.check_glyphs(monsters) {
+            problem
+        } else {
+            return Ok(());
+        };
+        Err(format!("prefab {:?}: {}", self.name, problem))
+    }
+
+    /// what's wrong with the characters of the layout, if anything
+    fn check_glyphs(&self, monsters: &[MonsterTemplate]) -> Option<String> {
+        let (width, height) = (self.width(), self.height());
+        let mut entrances = 0;
+        for ((x, y), glyph) in self.glyphs() {
+            match self.legend_for(glyph) {
+                None if glyph != ' ' => {
+                    return Some(format!("{:?} in its layout isn't in its legend", glyph))
+                }
+                Some(Legend::Monster(name)) if !monsters.iter().any(|m| m.name == name) => {
+                    return Some(format!("there is no monster called {:?}", name))
+                }
+                Some(Legend::Entrance) => {
+                    let on_side = x == 0 || x == width - 1;
+                    let on_end = y == 0 || y == height - 1;
+                    // a corridor is dug straight out of an entrance, which
+                    // only makes sense on one side of the prefab
+                    if on_side == on_end {
+                        return Some(format!(
+                            "the entrance at ({}, {}) must be on its edge, but not in a corner",
+                            x, y
+                        ));
+                    }
+                    entrances += 1;
+                }
+                _ => {}
+            }
+        }
+        if entrances == 0 {
+            return Some("it has no entrance, so it could never be reached".into());
+        }
+        None
+    }
+}
+
+/// parse and validate prefab definitions written in RON, checking that the
+/// monsters they place are among `monsters`
+pub fn parse_prefabs(source: &str, monsters: &[MonsterTemplate]) -> Result<Vec<Prefab>, String> {
+    let prefabs: Vec<Prefab> = ron::from_str(source).map_err(|e| e.to_string())?;
+
+    let mut names = HashSet::new();
+    for prefab in &prefabs {
+        prefab.validate(monsters)?;
+        if !names.insert(prefab.name.as_str()) {
+            return Err(format!("prefab {:?} is defined more than once", prefab.name));
+        }
+    }
+    Ok(prefabs)
+}
+
+/// read the prefab definitions from a file, with errors that point at the file
+pub fn load_prefabs(
+    path: impl AsRef<Path>,
+    monsters: &[MonsterTemplate],
+) -> Result<Vec<Prefab>, Box<dyn Error>> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let prefabs =
+        parse_prefabs(&source, monsters).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(prefabs)
+}
+
+/// Stamp the prefabs allowed at this depth into the level, each on a roll of
+/// its `chance`. A prefab goes either into solid rock, with a corridor dug from
+/// each of its entrances to the nearest floor, or into open floor with room to
+/// walk all the way around it, so the level can still be walked end to end.
+/// Prefabs stay away from the start, the exit and the doorways, and are left
+/// out when there is nowhere they fit.
+pub struct Vaults<'a> {
+    pub prefabs: &'a [Prefab],
+    pub monsters: &'a [MonsterTemplate],
+}
+
+impl MapBuilder for Vaults<'_> {
+    fn build(&self, data: &mut BuildData, rng: &mut GameRng) {
+        let mut placed = vec![];
+        let level = data.level;
+        for prefab in self.prefabs.iter().filter(|p| p.spawns_at(level)) {
+            if rng.gen_range(0..100) >= prefab.chance {
+                continue;
+            }
+            if let Some((x, y)) = find_spot(prefab, data, &placed, rng) {
+                placed.push(Rect::new(x, y, prefab.width() - 1, prefab.height() - 1));
+                self.stamp(prefab, (x, y), data, &placed);
+            }
+        }
+    }
+}
+
+impl Vaults<'_> {
+    /// put the prefab's tiles, monsters and items on the map with its top left at `at`
+    fn stamp(&self, prefab: &Prefab, at: (i32, i32), data: &mut BuildData, placed: &[Rect]) {
+        let width = prefab.width();
+        for ((dx, dy), glyph) in prefab.glyphs() {
+            let (x, y) = (at.0 + dx, at.1 + dy);
+            let legend = match prefab.legend_for(glyph) {
+                Some(legend) => legend,
+                None => continue,
+            };
+            data.map[x as usize][y as usize] = match legend {
+                Legend::Wall => Tile::wall(),
+                Legend::Tile(kind) => Tile::new(kind),
+                _ => Tile::empty(),
+            };
+            match legend {
+                Legend::Entrance => {
+                    data.doorways.push((x, y));
+                    // the tile just outside, on whichever side the entrance is on
+                    let outside = if dx == 0 {
+                        (x - 1, y)
+                    } else if dx == width - 1 {
+                        (x + 1, y)
+                    } else if dy == 0 {
+                        (x, y - 1)
+                    } else {
+                        (x, y + 1)
+                    };
+                    dig_to_floor(&mut data.map, outside, placed);
+                }
+                Legend::Monster(name) => {
+                    if let Some(template) = self.monsters.iter().find(|m| m.name == name) {
+                        data.spawns.push(template.spawn(x, y));
+                    }
+                }
+                Legend::Item(item) => data.spawns.push(item.spawn(x, y)),
+                Legend::Wall | Legend::Floor | Legend::Tile(_) => {}
+            }
+        }
+    }
+}
+
+/// Look for somewhere the prefab fits: a spot that, with a tile to spare all
+/// around, is either all rock or all floor, and has none of the start, the
+/// exit, the doorways, the spawns or the other prefabs in it.
+fn find_spot(
+    prefab: &Prefab,
+    data: &BuildData,
+    placed: &[Rect],
+    rng: &mut GameRng,
+) -> Option<(i32, i32)> {
+    let map_width = data.map.len() as i32;
+    let map_height = data.map[0].len() as i32;
+    let (width, height) = (prefab.width(), prefab.height());
+    // keep the spare tile clear of the map's edge, so corridors can go around
+    if width + 4 > map_width || height + 4 > map_height {
+        return None;
+    }
+
+    for _ in 0..PLACEMENT_TRIES {
+        let x = rng.gen_range(2..=map_width - 2 - width);
+        let y = rng.gen_range(2..=map_height - 2 - height);
+        let area = Rect::new(x - 1, y - 1, width + 1, height + 1);
+
+        let rock = data.map[x as usize][y as usize].blocked();
+        let uniform = (area.x1..=area.x2)
+            .all(|ax| (area.y1..=area.y2).all(|ay| data.map[ax as usize][ay as usize].blocked() == rock));
+        let taken = data.start.is_some_and(|start| area.contains(start))
+            || data.exit.is_some_and(|exit| area.contains(exit))
+            || data.doorways.iter().any(|&doorway| area.contains(doorway))
+            || data.spawns.iter().any(|object| area.contains(object.pos()))
+            || placed.iter().any(|other| area.intersects_with(other));
+        if uniform && !taken {
+            return Some((x, y));
+        }
+    }
+    None
+}
+
+/// Dig the shortest corridor from `from` to the nearest floor, going around
+/// the prefabs in `placed`. Nothing is dug if `from` is floor already.
+fn dig_to_floor(map: &mut Map, from: (i32, i32), placed: &[Rect]) {
+    let width = map.len() as i32;
+    let height = map[0].len() as i32;
+    let mut came_from = vec![vec![None; height as usize]; width as usize];
+    let mut queue = VecDeque::from([from]);
+    came_from[from.0 as usize][from.1 as usize] = Some(from);
+
+    while let Some((x, y)) = queue.pop_front() {
+        if !map[x as usize][y as usize].blocked() {
+            // found it: dig back along the way that was taken to get here
+            let mut tile = (x, y);
+            while tile != from {
+                tile = came_from[tile.0 as usize][tile.1 as usize].unwrap_or(from);
+                map[tile.0 as usize][tile.1 as usize] = Tile::empty();
+            }
+            map[from.0 as usize][from.1 as usize] = Tile::empty();
+            return;
+        }
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let next = (x + dx, y + dy);
+            // the edge of the map stays solid
+            if next.0 < 1 || next.1 < 1 || next.0 >= width - 1 || next.1 >= height - 1 {
+                continue;
+            }
+            if placed.iter().any(|prefab| prefab.contains(next))
+                || came_from[next.0 as usize][next.1 as usize].is_some()
+            {
+                continue;
+            }
+            came_from[next.0 as usize][next.1 as usize] = Some((x, y));
+            queue.push_back(next);
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use rand::Rng;
+use tcod::colors::*;
+
+use crate::fov::line;
+use crate::game::{Game, PLAYER};
+use crate::item::{Item, UseResult};
+use crate::map::Map;
+use crate::object::{DamageKind, Object};
+
+/// how far a throwing knife can be thrown
+pub const THROW_RANGE: i32 = 6;
+/// the damage an arrow does, before the target's defense
+const ARROW_DAMAGE: i32 = 6;
+/// the percent chance of a fired arrow breaking, instead of lying where it fell
+const ARROW_BREAK_CHANCE: u32 = 50;
+/// ranged monsters back away when the player gets closer than this
+pub const KEEP_DISTANCE: f32 = 3.0;
+
+/// Something that flew across the map during the last step, for the
+/// frontend to animate.
+#[derive(Clone, Debug)]
+pub struct Shot {
+    /// the tiles it flew through, in order
+    pub path: Vec<(i32, i32)>,
+    pub glyph: char,
+    pub color: Color,
+}
+
+/// Follow a projectile from `from` towards `to`. It stops before the first
+/// tile that blocks, or on the first blocking object in its way. Returns the
+/// tiles it flew through, and what it hit if anything.
+pub fn line_of_fire(
+    from: (i32, i32),
+    to: (i32, i32),
+    map: &Map,
+    objects: &[Object],
+) -> (Vec<(i32, i32)>, Option<usize>) {
+    let mut path = vec![];
+    for (x, y) in line(from, to) {
+        let on_map = x >= 0 && y >= 0 && x < map.len() as i32 && y < map[0].len() as i32;
+        if !on_map || map[x as usize][y as usize].blocked() {
+            break;
+        }
+        path.push((x, y));
+        let hit = objects
+            .iter()
+            .position(|object| object.blocks && object.pos() == (x, y));
+        if hit.is_some() {
+            return (path, hit);
+        }
+    }
+    (path, None)
+}
+
+/// whether a projectile from `from` would get to `to` with nothing in the way
+pub fn clear_shot(from: (i32, i32), to: (i32, i32), map: &Map, objects: &[Object]) -> bool {
+    let (path, hit) = line_of_fire(from, to, map, objects);
+    path.last() == Some(&to) && hit.is_none_or(|id| objects[id].pos() == to)
+}
+
+/// what a projectile flying from `from` to `to` looks like: a line pointing the way it goes
+fn projectile_glyph(from: (i32, i32), to: (i32, i32)) -> char {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    if dy.abs() * 2 < dx.abs() {
+        '-'
+    } else if dx.abs() * 2 < dy.abs() {
+        '|'
+    } else if (dx > 0) == (dy > 0) {
+        '\\'
+    } else {
+        '/'
+    }
+}
+
+/// Send a projectile from `shooter` at `target`, remembering it so it can be
+/// drawn. Returns the tile it came down on and what it hit, if anything.
+fn shoot(
+    shooter: usize,
+    target: (i32, i32),
+    glyph: char,
+    color: Color,
+    game: &mut Game,
+    objects: &[Object],
+) -> ((i32, i32), Option<usize>) {
+    let from = objects[shooter].pos();
+    let (path, hit) = line_of_fire(from, target, &game.map, objects);
+    let landing = path.last().copied().unwrap_or(from);
+    game.shots.push(Shot { path, glyph, color });
+    (landing, hit)
+}
+
+/// hurt the fighter that was hit, giving the shooter the XP if it died
+fn hurt(
+    shooter: usize,
+    hit: usize,
+    damage: i32,
+    (text, color): (String, Color),
+    game: &mut Game,
+    objects: &mut [Object],
+) {
+    if damage > 0 {
+        game.messages.add(text, color);
+        if let Some(xp) = objects[hit].take_damage(damage, game) {
+            objects[shooter].gain_xp(xp);
+        }
+    } else {
+        game.messages
+            .add(format!("It bounces off {} harmlessly.", objects[hit].name), WHITE);
+    }
+}
+
+/// take one from the stack at `inventory_id`, returning whether any are left
+fn take_one(inventory_id: usize, game: &mut Game) -> bool {
+    let stack = &mut game.inventory[inventory_id];
+    stack.count -= 1;
+    stack.count > 0
+}
+
+/// put a single piece of ammunition on the floor at `(x, y)`
+fn drop_ammo(item: Item, (x, y): (i32, i32), objects: &mut Vec<Object>) {
+    let mut ammo = item.spawn(x, y);
+    ammo.count = 1;
+    objects.push(ammo);
+}
+
+/// How far the player can shoot, or why they can't shoot at all: there's no
+/// ranged weapon on, or no arrows left for it.
+pub fn fire_range(game: &Game, objects: &[Object]) -> Result<i32, &'static str> {
+    let range = objects[PLAYER]
+        .get_all_equipped(game)
+        .iter()
+        .map(|equipment| equipment.range)
+        .max()
+        .unwrap_or(0);
+    if range == 0 {
+        Err("You have nothing to shoot with.")
+    } else if !game.inventory.iter().any(|item| item.item == Some(Item::Arrows)) {
+        Err("You have no arrows left.")
+    } else {
+        Ok(range)
+    }
+}
+
+/// Fire the player's ranged weapon at `target`, using up an arrow. Returns
+/// whether anything was shot.
+pub fn player_fire(target: (i32, i32), game: &mut Game, objects: &mut Vec<Object>) -> bool {
+    let range = match fire_range(game, objects) {
+        Ok(range) => range,
+        Err(reason) => {
+            game.messages.add(reason, RED);
+            return false;
+        }
+    };
+    // there's a stack of arrows, or `fire_range` would have said so
+    let arrows = game
+        .inventory
+        .iter()
+        .position(|item| item.item == Some(Item::Arrows))
+        .unwrap();
+    if target == objects[PLAYER].pos() || objects[PLAYER].distance(target.0, target.1) > range as f32 {
+        game.messages.add("That is out of range.", RED);
+        return false;
+    }
+
+    if !take_one(arrows, game) {
+        game.inventory.remove(arrows);
+    }
+    let glyph = projectile_glyph(objects[PLAYER].pos(), target);
+    let (landing, hit) = shoot(PLAYER, target, glyph, LIGHT_SEPIA, game, objects);
+    match hit {
+        Some(hit) if objects[hit].fighter.is_some() => {
+            let damage = ARROW_DAMAGE - objects[hit].defense(game) / 4;
+            let text = format!("Your arrow hits {} for {} hit points.", objects[hit].name, damage);
+            hurt(PLAYER, hit, damage, (text, WHITE), game, objects);
+        }
+        _ => game.messages.add("Your arrow hits nothing.", WHITE),
+    }
+    // arrows that don't break can be picked up again
+    if game.rng.gen_range(0..100) >= ARROW_BREAK_CHANCE {
+        drop_ammo(Item::Arrows, landing, objects);
+    }
+    true
+}
+
+/// Throw one of the throwing knives at `inventory_id` at `target`. A knife
+/// hurts like a blow from the player, and lands where it stopped.
+pub fn throw_knife(
+    inventory_id: usize,
+    target: Option<(i32, i32)>,
+    game: &mut Game,
+    objects: &mut Vec<Object>,
+) -> UseResult {
+    let target = match target {
+        Some(tile)
+            if tile != objects[PLAYER].pos()
+                && objects[PLAYER].distance(tile.0, tile.1) <= THROW_RANGE as f32 =>
+        {
+            tile
+        }
+        _ => {
+            game.messages.add("That is out of reach.", RED);
+            return UseResult::Cancelled;
+        }
+    };
+
+    let glyph = projectile_glyph(objects[PLAYER].pos(), target);
+    let (landing, hit) = shoot(PLAYER, target, glyph, LIGHT_GREY, game, objects);
+    match hit {
+        Some(hit) if objects[hit].fighter.is_some() => {
+            let damage = objects[PLAYER].damage_against(&objects[hit], DamageKind::Physical, game);
+            let text = format!("Your knife hits {} for {} hit points.", objects[hit].name, damage);
+            hurt(PLAYER, hit, damage, (text, WHITE), game, objects);
+        }
+        _ => game.messages.add("Your knife hits nothing.", WHITE),
+    }
+    drop_ammo(Item::ThrowingKnives, landing, objects);
+
+    // the last one is gone from the inventory when it's used up
+    if take_one(inventory_id, game) {
+        UseResult::UsedAndKept
+    } else {
+        game.inventory.remove(inventory_id);
+        UseResult::UsedUp
+    }
+}
+
+/// a monster shoots at `target` with its usual kind of attack
+pub fn monster_shoot(id: usize, target: (i32, i32), game: &mut Game, objects: &mut [Object]) {
+    let kind = objects[id].fighter.map_or(DamageKind::Physical, |f| f.attack);
+    let glyph = match kind {
+        DamageKind::Physical => projectile_glyph(objects[id].pos(), target),
+        DamageKind::Magical => '*',
+    };
+    let (_, hit) = shoot(id, target, glyph, kind.color(), game, objects);
+    if let Some(hit) = hit.filter(|&hit| objects[hit].fighter.is_some()) {
+        let damage = objects[id].damage_against(&objects[hit], kind, game);
+        let text = match kind {
+            DamageKind::Physical => format!(
+                "{} shoots {} for {} hit points.",
+                objects[id].name, objects[hit].name, damage
+            ),
+            DamageKind::Magical => format!(
+                "{} hurls dark magic at {} for {} hit points.",
+                objects[id].name, objects[hit].name, damage
+            ),
+        };
+        hurt(id, hit, damage, (text, kind.color()), game, objects);
+    }
+}
@@ -0,0 +1,112 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::Command;
+
+/// Version of the replay format. Replays only reproduce a run when played back
+/// by the same version of the game, with the same data in `assets/`.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// the first line of a replay file; every line after it is one command
+#[derive(Serialize, Deserialize)]
+struct ReplayHeader {
+    version: u32,
+    seed: u64,
+}
+
+/// A whole run: the seed it started from and every command it was given.
+/// Feeding the commands to a fresh `World` with that seed plays the run again.
+pub struct Replay {
+    pub seed: u64,
+    pub commands: Vec<Command>,
+}
+
+impl Replay {
+    /// read a replay written by a `Recorder`
+    pub fn load(path: impl AsRef<Path>) -> Result<Replay, Box<dyn Error>> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        let (seed, commands) = parse_replay(&source)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Replay { seed, commands })
+    }
+}
+
+/// split a replay into its seed and its commands, checking the version
+fn parse_replay(source: &str) -> Result<(u64, Vec<Command>), String> {
+    let lines: Vec<&str> = source.lines().collect();
+    let header: ReplayHeader = serde_json::from_str(lines.first().copied().unwrap_or_default())
+        .map_err(|e| format!("not a replay ({})", e))?;
+    if header.version != REPLAY_VERSION {
+        return Err(format!(
+            "this replay was written by version {} of the replay format, but the game reads version {}",
+            header.version, REPLAY_VERSION
+        ));
+    }
+
+    let mut commands = vec![];
+    for (index, line) in lines.iter().enumerate().skip(1) {
+        match serde_json::from_str(line) {
+            Ok(command) => commands.push(command),
+            // a crash may have cut the last line short; everything before it is still good
+            Err(_) if index == lines.len() - 1 => break,
+            Err(e) => return Err(format!("line {}: {}", index + 1, e)),
+        }
+    }
+    Ok((header.seed, commands))
+}
+
+/// Writes every command of a run to a replay file as it's given, so even a
+/// crash leaves everything up to that point behind.
+pub struct Recorder {
+    file: File,
+}
+
+impl Recorder {
+    /// start a new replay file for a run started from `seed`
+    pub fn create(path: impl AsRef<Path>, seed: u64) -> Result<Recorder, Box<dyn Error>> {
+        let mut file = File::create(path)?;
+        let header = serde_json::to_string(&ReplayHeader {
+            version: REPLAY_VERSION,
+            seed,
+        })?;
+        writeln!(file, "{}", header)?;
+        Ok(Recorder { file })
+    }
+
+    /// Keep recording a run that was saved after being given `commands` commands.
+    /// Anything recorded after that point (say, before a crash) never made it into
+    /// the save, so it's dropped from the replay too.
+    pub fn resume(
+        path: impl AsRef<Path>,
+        seed: u64,
+        commands: u64,
+    ) -> Result<Recorder, Box<dyn Error>> {
+        let path = path.as_ref();
+        let (recorded_seed, mut recorded) = parse_replay(&fs::read_to_string(path)?)?;
+        if recorded_seed != seed {
+            return Err(format!("the replay in {} is of another run", path.display()).into());
+        }
+        if (recorded.len() as u64) < commands {
+            return Err(format!("the replay in {} is missing part of the run", path.display()).into());
+        }
+        recorded.truncate(commands as usize);
+
+        let mut recorder = Recorder::create(path, seed)?;
+        for command in recorded {
+            recorder.record(command)?;
+        }
+        Ok(recorder)
+    }
+
+    /// add one command to the end of the replay
+    pub fn record(&mut self, command: Command) -> Result<(), Box<dyn Error>> {
+        writeln!(self.file, "{}", serde_json::to_string(&command)?)?;
+        Ok(())
+    }
+}
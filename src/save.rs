@@ -0,0 +1,73 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Game, World};
+use crate::monsters::MonsterTemplate;
+use crate::object::Object;
+use crate::prefab::Prefab;
+
+/// Version of the save format. Bump this whenever the shape of anything that
+/// gets saved changes, so old saves are refused instead of misread.
+pub const SAVE_VERSION: u32 = 14;
+
+#[derive(Serialize)]
+struct SaveFileRef<'a> {
+    version: u32,
+    game: &'a Game,
+    objects: &'a [Object],
+}
+
+/// just enough of a save to find out which version wrote it
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct SaveFile {
+    game: Game,
+    objects: Vec<Object>,
+}
+
+/// write the whole run (map, objects, messages and RNG state) to `path`
+pub fn save_game(world: &World, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+    let save_data = serde_json::to_string(&SaveFileRef {
+        version: SAVE_VERSION,
+        game: &world.game,
+        objects: &world.objects,
+    })?;
+    let mut file = File::create(path)?;
+    file.write_all(save_data.as_bytes())?;
+    Ok(())
+}
+
+/// read a run back from `path`, refusing saves written by another version of the game.
+/// Levels generated from now on will spawn from `monsters` and `prefabs`.
+pub fn load_game(
+    path: impl AsRef<Path>,
+    monsters: Vec<MonsterTemplate>,
+    prefabs: Vec<Prefab>,
+) -> Result<World, Box<dyn Error>> {
+    let mut json_save_state = String::new();
+    let mut file = File::open(path)?;
+    file.read_to_string(&mut json_save_state)?;
+
+    // check the version first, so an old save gives a clear error instead of
+    // whatever serde makes of the fields that changed
+    let version = serde_json::from_str::<SaveHeader>(&json_save_state)?.version;
+    if version != Some(SAVE_VERSION) {
+        let found = version.map_or("an unknown version".into(), |v| format!("version {}", v));
+        return Err(format!(
+            "this save was written by {} of the save format, but the game reads version {}",
+            found, SAVE_VERSION
+        )
+        .into());
+    }
+
+    let save: SaveFile = serde_json::from_str(&json_save_state)?;
+    Ok(World::from_game(save.game, save.objects, monsters, prefabs))
+}
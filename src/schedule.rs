@@ -0,0 +1,86 @@
+/// how fast the player and ordinary monsters are: one action per turn
+pub const NORMAL_SPEED: i32 = 100;
+
+/// how much energy an actor needs before it can act
+pub const ACTION_ENERGY: i32 = 100;
+
+/// A turn is split into this many ticks, so quick actions and odd speeds
+/// interleave properly. Speeds should be multiples of it.
+pub const TICKS_PER_TURN: i32 = 10;
+
+/// The things an actor can spend its energy on, which don't all take as long.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Move,
+    Attack,
+    Wait,
+    /// drink a potion
+    Quaff,
+    /// read a scroll
+    Read,
+    PickUp,
+    Drop,
+    /// open or close a door
+    Door,
+    /// put on or take off a piece of equipment
+    Equip,
+}
+
+impl Action {
+    /// how much energy doing this takes
+    pub fn cost(self) -> i32 {
+        match self {
+            Action::Move
+            | Action::Attack
+            | Action::Wait
+            | Action::Read
+            | Action::Door
+            | Action::Equip => 100,
+            Action::Quaff | Action::PickUp | Action::Drop => 50,
+        }
+    }
+}
+
+/// Whatever holds the actors the scheduler hands out time to. Actors are
+/// numbered from 0 and don't need to be able to act (items, corpses...).
+pub trait Actors {
+    /// how many actors there are, acting or not
+    fn count(&self) -> usize;
+    /// how fast the actor is, or None if it can't act
+    fn speed(&self, id: usize) -> Option<i32>;
+    /// the energy the actor has stored up
+    fn energy(&mut self, id: usize) -> &mut i32;
+    /// let the actor do something, returning what it did
+    fn act(&mut self, id: usize) -> Action;
+    /// how many ticks have passed since the start
+    fn time(&mut self) -> &mut u64;
+    /// called every time a whole turn has passed
+    fn end_turn(&mut self);
+}
+
+/// Let time pass until actor `id` has the energy to act again (or can't act
+/// anymore). Every tick each actor gains energy according to its speed, and
+/// every other actor that has enough acts right away, paying for it.
+pub fn run_until_ready(actors: &mut impl Actors, id: usize) {
+    while *actors.energy(id) < ACTION_ENERGY && actors.speed(id).is_some() {
+        for other in 0..actors.count() {
+            if let Some(speed) = actors.speed(other) {
+                *actors.energy(other) += speed / TICKS_PER_TURN;
+            }
+            if other == id {
+                continue;
+            }
+            // fast actors may have enough to go more than once; stop if it died meanwhile
+            while *actors.energy(other) >= ACTION_ENERGY && actors.speed(other).is_some() {
+                let action = actors.act(other);
+                *actors.energy(other) -= action.cost();
+            }
+        }
+
+        let time = actors.time();
+        *time += 1;
+        if time.is_multiple_of(TICKS_PER_TURN as u64) {
+            actors.end_turn();
+        }
+    }
+}
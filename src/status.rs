@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use tcod::colors::*;
+
+use crate::game::{Game, PLAYER};
+use crate::object::Object;
+
+// stacked poison never does more than this much damage per turn
+const MAX_POTENCY: i32 = 5;
+
+/// The kinds of effects that can last on an object over several turns.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StatusKind {
+    /// loses `potency` HP every turn
+    Poison,
+    /// stumbles around in random directions
+    Confusion,
+    /// can't act at all; counts down the turns it takes away, not the turns that pass
+    Stun,
+    /// regains `potency` HP every turn
+    Regeneration,
+}
+
+impl StatusKind {
+    /// the name shown in the GUI panel
+    pub fn label(self) -> &'static str {
+        match self {
+            StatusKind::Poison => "Poison",
+            StatusKind::Confusion => "Confused",
+            StatusKind::Stun => "Stunned",
+            StatusKind::Regeneration => "Regen",
+        }
+    }
+
+    /// the word used in messages like "<object> is no longer ..."
+    pub fn adjective(self) -> &'static str {
+        match self {
+            StatusKind::Poison => "poisoned",
+            StatusKind::Confusion => "confused",
+            StatusKind::Stun => "stunned",
+            StatusKind::Regeneration => "regenerating",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            StatusKind::Poison => LIGHT_GREEN,
+            StatusKind::Confusion => LIGHT_CYAN,
+            StatusKind::Stun => LIGHT_YELLOW,
+            StatusKind::Regeneration => LIGHT_PINK,
+        }
+    }
+
+    /// Poison gets worse with each new dose; everything else just lasts longer.
+    fn stacks(self) -> bool {
+        self == StatusKind::Poison
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    /// how many more turns it lasts
+    pub turns: i32,
+    /// how strong it is (HP per turn for poison and regeneration, unused otherwise)
+    #[serde(default)]
+    pub potency: i32,
+}
+
+impl Object {
+    /// apply an effect, following the stacking rules when one of its kind is already active:
+    /// poison adds up its potency (up to a cap), anything else is refreshed to the stronger
+    /// potency. Either way, it then lasts as long as the longer of the two.
+    pub fn add_effect(&mut self, effect: StatusEffect) {
+        match self.effects.iter_mut().find(|e| e.kind == effect.kind) {
+            Some(existing) => {
+                existing.potency = if effect.kind.stacks() {
+                    (existing.potency + effect.potency).min(MAX_POTENCY)
+                } else {
+                    existing.potency.max(effect.potency)
+                };
+                existing.turns = existing.turns.max(effect.turns);
+            }
+            None => self.effects.push(effect),
+        }
+    }
+
+    pub fn has_effect(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|e| e.kind == kind)
+    }
+}
+
+/// Run one turn of every effect on the object: poison hurts, regeneration
+/// heals, and everything but stun counts down, wearing off when it reaches 0.
+/// Stun is counted down by `lose_stunned_turn` instead.
+pub fn tick_effects(id: usize, game: &mut Game, objects: &mut [Object]) {
+    if objects[id].effects.is_empty() {
+        return;
+    }
+    // only tell the player about what they can see
+    let visible = id == PLAYER || game.fov.is_in_fov(objects[id].x, objects[id].y);
+
+    for effect in objects[id].effects.clone() {
+        let object = &mut objects[id];
+        if !object.alive {
+            break;
+        }
+        match effect.kind {
+            StatusKind::Poison => {
+                if visible {
+                    game.messages.add(
+                        format!("{} suffers {} poison damage.", object.name, effect.potency),
+                        StatusKind::Poison.color(),
+                    );
+                }
+                object.take_damage(effect.potency, game);
+            }
+            StatusKind::Regeneration => object.heal(effect.potency, game),
+            StatusKind::Confusion | StatusKind::Stun => {}
+        }
+    }
+
+    let object = &mut objects[id];
+    for effect in object.effects.iter_mut() {
+        if effect.kind != StatusKind::Stun {
+            effect.turns -= 1;
+        }
+    }
+    let (expired, active): (Vec<_>, Vec<_>) =
+        object.effects.drain(..).partition(|effect| effect.turns <= 0);
+    if visible && object.alive {
+        for effect in expired {
+            game.messages.add(
+                format!("{} is no longer {}.", object.name, effect.kind.adjective()),
+                WHITE,
+            );
+        }
+    }
+    object.effects = active;
+}
+
+/// If the object is stunned, spend one of the turns it has to lose and return
+/// true. Stun wears off this way, rather than with time, so even a 1-turn stun
+/// takes a turn away before it's over.
+pub fn lose_stunned_turn(id: usize, game: &mut Game, objects: &mut [Object]) -> bool {
+    let object = &mut objects[id];
+    let stun = match object.effects.iter_mut().find(|e| e.kind == StatusKind::Stun) {
+        Some(stun) => stun,
+        None => return false,
+    };
+    stun.turns -= 1;
+    if stun.turns <= 0 {
+        object.effects.retain(|e| e.kind != StatusKind::Stun);
+        if id == PLAYER || game.fov.is_in_fov(object.x, object.y) {
+            game.messages.add(
+                format!("{} is no longer {}.", object.name, StatusKind::Stun.adjective()),
+                WHITE,
+            );
+        }
+    }
+    true
+}
@@ -0,0 +1,85 @@
+use crate::game::{Game, PLAYER};
+use crate::item::Item;
+use crate::object::Object;
+use crate::ranged::{fire_range, line_of_fire};
+
+/// How a target is picked for an item or a shot: how far away it may be, and
+/// what to show while it's being picked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Targeting {
+    /// how far from the player the target may be, if there's a limit
+    pub range: Option<f32>,
+    /// whether it flies there in a straight line, stopping at whatever is in the way
+    pub projectile: bool,
+    /// how far around the target it reaches, 0 for the target alone
+    pub radius: i32,
+}
+
+impl Targeting {
+    /// how a target is picked for the item, or None if it doesn't need one
+    pub fn item(item: Item) -> Option<Targeting> {
+        let range = item.target_range()?;
+        Some(Targeting {
+            range: Some(range),
+            projectile: item == Item::ThrowingKnives,
+            radius: item.area_radius(),
+        })
+    }
+
+    /// how a target is picked for the player's ranged weapon, or why nothing
+    /// can be shot (see `fire_range`)
+    pub fn fire(game: &Game, objects: &[Object]) -> Result<Targeting, &'static str> {
+        let range = fire_range(game, objects)?;
+        Ok(Targeting {
+            range: Some(range as f32),
+            projectile: true,
+            radius: 0,
+        })
+    }
+
+    /// whether `(x, y)` can be picked: it has to be seen, and in range
+    pub fn allows(&self, (x, y): (i32, i32), game: &Game, objects: &[Object]) -> bool {
+        let player = &objects[PLAYER];
+        let in_range = self.range.is_none_or(|range| player.distance(x, y) <= range);
+        let not_self = !self.projectile || player.pos() != (x, y);
+        game.fov.is_in_fov(x, y) && in_range && not_self
+    }
+
+    /// the tiles it would fly through on its way to `target`, if it flies
+    pub fn path(&self, target: (i32, i32), game: &Game, objects: &[Object]) -> Vec<(i32, i32)> {
+        if !self.projectile {
+            return vec![];
+        }
+        line_of_fire(objects[PLAYER].pos(), target, &game.map, objects).0
+    }
+
+    /// the tiles it would reach around `target`, the target's own included
+    pub fn area(&self, (x, y): (i32, i32), game: &Game) -> Vec<(i32, i32)> {
+        let radius = self.radius;
+        (x - radius..=x + radius)
+            .flat_map(|tx| (y - radius..=y + radius).map(move |ty| (tx, ty)))
+            .filter(|&(tx, ty)| {
+                (((tx - x).pow(2) + (ty - y).pow(2)) as f32).sqrt() <= radius as f32
+                    && game.fov.is_in_fov(tx, ty)
+            })
+            .collect()
+    }
+}
+
+/// the monsters the player can see, closest first, that could be picked as targets
+pub fn visible_enemies(targeting: &Targeting, game: &Game, objects: &[Object]) -> Vec<usize> {
+    let mut enemies: Vec<usize> = (0..objects.len())
+        .filter(|&id| {
+            let object = &objects[id];
+            id != PLAYER
+                && object.alive
+                && object.fighter.is_some()
+                && targeting.allows(object.pos(), game, objects)
+        })
+        .collect();
+    enemies.sort_by(|&a, &b| {
+        let distance = |id: usize| objects[PLAYER].distance_to(&objects[id]);
+        distance(a).total_cmp(&distance(b))
+    });
+    enemies
+}
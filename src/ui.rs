@@ -1,8 +1,16 @@
 use tcod::colors::*;
 use tcod::console::*;
-use tcod::input::Key;
-use tcod::input::KeyCode::*;
+use tcod::input::Mouse;
 
+use unkindred_hearts::equipment::{equipped_in_slot, Slot};
+use unkindred_hearts::fov::Fov;
+use unkindred_hearts::game::Game;
+use unkindred_hearts::map::Map;
+use unkindred_hearts::object::Object;
+
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+#[allow(clippy::too_many_arguments)]
 pub fn render_bar(
     panel: &mut Offscreen,
     x: i32,
@@ -34,26 +42,186 @@ pub fn render_bar(
         y,
         BackgroundFlag::None,
         TextAlignment::Center,
-        &format!("{}: {}/{}", name, value, maximum),
+        format!("{}: {}/{}", name, value, maximum),
     );
 }
 
-pub struct Messages {
-    messages: Vec<(String, Color)>,
+pub fn render_map(con: &mut Offscreen, map: &Map, fov: &Fov) {
+    // go through all tiles, and set their background color
+    for y in 0..map[0].len() {
+        for (x, column) in map.iter().enumerate() {
+            let tile = &column[y];
+            let color = if fov.is_in_fov(x as i32, y as i32) {
+                tile.kind.light_color()
+            } else {
+                tile.kind.dark_color()
+            };
+
+            if tile.explored {
+                // show explored tiles only (any visible tile is explored already)
+                con.put_char_ex(x as i32, y as i32, tile.kind.glyph(), color, BLACK);
+            }
+        }
+    }
+}
+
+/// set the color and then draw the character that represents this object at its position
+pub fn draw_object(object: &Object, con: &mut dyn Console) {
+    con.set_default_foreground(object.color);
+    con.put_char(object.x, object.y, object.char, BackgroundFlag::None);
 }
 
-impl Messages {
-    pub fn new() -> Self {
-        Self { messages: vec![] }
+/// return a string with the names of all objects under the mouse
+pub fn get_names_under_mouse(mouse: Mouse, objects: &[Object], fov: &Fov) -> String {
+    let (x, y) = (mouse.cx as i32, mouse.cy as i32);
+
+    // create a list with the names of all objects at the mouse's coordinates and in FOV
+    let names = objects
+        .iter()
+        .filter(|obj| obj.pos() == (x, y) && fov.is_in_fov(obj.x, obj.y))
+        .map(|obj| obj.name.clone())
+        .collect::<Vec<_>>();
+
+    names.join(", ") // join the names, separated by commas
+}
+
+/// show a list of lettered options and wait for one to be picked
+pub fn menu<T: AsRef<str>>(header: &str, options: &[T], width: i32, root: &mut Root) -> Option<usize> {
+    assert!(
+        options.len() <= 26,
+        "Cannot have a menu with more than 26 options."
+    );
+
+    // calculate total height for the header (after auto-wrap) and one line per option
+    let header_height = if header.is_empty() {
+        0
+    } else {
+        root.get_height_rect(0, 0, width, SCREEN_HEIGHT, header)
+    };
+    let height = options.len() as i32 + header_height;
+
+    // create an off-screen console that represents the menu's window
+    let mut window = Offscreen::new(width, height);
+
+    // print the header, with auto-wrap
+    window.set_default_foreground(WHITE);
+    window.print_rect_ex(
+        0,
+        0,
+        width,
+        height,
+        BackgroundFlag::None,
+        TextAlignment::Left,
+        header,
+    );
+
+    // print all the options
+    for (index, option_text) in options.iter().enumerate() {
+        let menu_letter = (b'a' + index as u8) as char;
+        let text = format!("({}) {}", menu_letter, option_text.as_ref());
+        window.print_ex(
+            0,
+            header_height + index as i32,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            text,
+        );
     }
 
-    /// add the new message as a tuple, with the text and the color
-    pub fn add<T: Into<String>>(&mut self, message: T, color: Color) {
-        self.messages.push((message.into(), color));
+    // blit the contents of "window" to the root console
+    let x = SCREEN_WIDTH / 2 - width / 2;
+    let y = SCREEN_HEIGHT / 2 - height / 2;
+    blit(&window, (0, 0), (width, height), root, (x, y), 1.0, 0.7);
+
+    // present the root console to the player and wait for a key-press
+    root.flush();
+    let key = root.wait_for_keypress(true);
+
+    // convert the ASCII code to an index; if it corresponds to an option, return it
+    if key.printable.is_alphabetic() {
+        let index = key.printable.to_ascii_lowercase() as usize - 'a' as usize;
+        if index < options.len() {
+            Some(index)
+        } else {
+            None
+        }
+    } else {
+        None
     }
+}
+
+// width of the inventory window
+const INVENTORY_WIDTH: i32 = 50;
+// width of the equipment window
+const EQUIPMENT_WIDTH: i32 = 60;
+
+/// show a menu with each item of the inventory as an option
+pub fn inventory_menu(inventory: &[Object], header: &str, root: &mut Root) -> Option<usize> {
+    // show a menu with each item of the inventory as an option
+    let options = if inventory.is_empty() {
+        vec!["Inventory is empty.".into()]
+    } else {
+        inventory
+            .iter()
+            .map(|item| match item.equipment {
+                // show which items are being worn
+                Some(equipment) if equipment.equipped => {
+                    format!("{} (on {})", item.name, equipment.slot)
+                }
+                // and how many are left of what stacks
+                _ if item.item.is_some_and(|kind| kind.stacks()) => {
+                    format!("{} ({})", item.name, item.count)
+                }
+                _ => item.name.clone(),
+            })
+            .collect()
+    };
+
+    let inventory_index = menu(header, &options, INVENTORY_WIDTH, root);
 
-    /// Create a `DoubleEndedIterator` over the messages
-    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &(String, Color)> {
-        self.messages.iter()
+    // if an item was chosen, return it
+    if !inventory.is_empty() {
+        inventory_index
+    } else {
+        None
     }
-}
\ No newline at end of file
+}
+
+/// Show the player's stats and what they have on in each slot. Returns the
+/// inventory index of what's in the chosen slot, if there's anything there.
+pub fn equipment_menu(player: &Object, game: &Game, root: &mut Root) -> Option<usize> {
+    let header = format!(
+        "Power {}  Defense {}  Magic {}  Magic defense {}  Max HP {}\n\n\
+         Press the key next to a slot to take off what's in it, or any other to cancel.\n",
+        player.power(game),
+        player.defense(game),
+        player.magic(game),
+        player.magic_defense(game),
+        player.max_hp(game),
+    );
+    let worn: Vec<_> = Slot::ALL
+        .iter()
+        .map(|&slot| equipped_in_slot(slot, &game.inventory))
+        .collect();
+    let options: Vec<String> = Slot::ALL
+        .iter()
+        .zip(&worn)
+        .map(|(slot, index)| match index {
+            Some(index) => {
+                let item = &game.inventory[*index];
+                let bonuses = item.equipment.map(|e| e.describe()).unwrap_or_default();
+                format!("{}: {} ({})", slot, item.name, bonuses)
+            }
+            None => format!("{}: nothing", slot),
+        })
+        .collect();
+
+    let choice = menu(&header, &options, EQUIPMENT_WIDTH, root)?;
+    worn.get(choice).copied().flatten()
+}
+
+/// show a message until any key is pressed
+pub fn msgbox(text: &str, width: i32, root: &mut Root) {
+    let options: &[&str] = &[];
+    menu(text, options, width, root);
+}
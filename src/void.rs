@@ -0,0 +1,213 @@
+use rand::Rng;
+use tcod::colors::*;
+
+use crate::builder::{BuildData, MapBuilder};
+use crate::game::{Game, GameRng, PLAYER};
+use crate::map::{from_dungeon_level, Tile, TileKind, Transition};
+use crate::object::{DamageKind, DeathCallback, Fighter, Object};
+use crate::schedule::{NORMAL_SPEED, TICKS_PER_TURN};
+
+pub const VOID_COLOR: Color = Color { r: 191, g: 63, b: 255 };
+
+// how the void spreads from its rifts
+const SPREAD_TURNS: u64 = 8; // turns between each spread
+const SPREAD_TILES: usize = 3; // tiles each rift taints every time it spreads
+pub const SPREAD_RADIUS: i32 = 10; // how far from its rift the void can reach
+// tainted ground around a rift when the level is made
+const RIFT_START_RADIUS: i32 = 1;
+// rifts are never put closer than this to where the player arrives
+const RIFT_MIN_DISTANCE: f32 = 12.0;
+const RIFT_PLACEMENT_TRIES: u32 = 100;
+
+// what the void does to those standing in it
+const VOID_HARM_TURNS: u64 = 3; // the player loses a hit point this often
+const VOID_HARM_DAMAGE: i32 = 1;
+pub const VOID_POWER_BONUS: i32 = 2; // monsters hit this much harder
+
+/// what the look mode says about a void rift
+pub const VOID_RIFT_DESCRIPTION: &str =
+    "A tear in the world that the void seeps out of. Destroy it, and its void fades away.";
+
+/// create a void rift at (x, y): it can't move or fight back, but the void
+/// spreads from it until it's destroyed
+pub fn void_rift(x: i32, y: i32) -> Object {
+    let mut rift = Object::new(x, y, '*', "void rift", VOID_COLOR, true);
+    rift.alive = true;
+    rift.fighter = Some(Fighter {
+        max_hp: 20,
+        hp: 20,
+        defense: 2,
+        power: 0,
+        magic: 0,
+        magic_defense: 4,
+        attack: DamageKind::Physical,
+        on_hit: None,
+        speed: NORMAL_SPEED,
+        xp: 60,
+        on_death: DeathCallback::VoidRift,
+    });
+    rift
+}
+
+pub fn is_void_rift(object: &Object) -> bool {
+    object.alive && object.fighter.is_some_and(|f| f.on_death == DeathCallback::VoidRift)
+}
+
+/// Called every turn: every few turns the void spreads out from each rift,
+/// and wears down the player if they're standing in it.
+pub fn tick_void(game: &mut Game, objects: &mut [Object]) {
+    let turn = game.time / TICKS_PER_TURN as u64;
+    if turn.is_multiple_of(SPREAD_TURNS) {
+        spread_void(game, objects);
+    }
+
+    let (x, y) = objects[PLAYER].pos();
+    if turn.is_multiple_of(VOID_HARM_TURNS)
+        && objects[PLAYER].alive
+        && game.map[x as usize][y as usize].kind == TileKind::VoidFloor
+    {
+        game.messages.add(
+            format!("The void gnaws at you for {} hit points.", VOID_HARM_DAMAGE),
+            VOID_COLOR,
+        );
+        objects[PLAYER].take_damage(VOID_HARM_DAMAGE, game);
+    }
+}
+
+/// Let the void taint a few more tiles of floor around each rift. It grows
+/// out from the ground already tainted, never further than `SPREAD_RADIUS`
+/// from its rift, and can't cross walls, water or lava.
+pub fn spread_void(game: &mut Game, objects: &[Object]) {
+    let mut seen_spreading = false;
+    for rift in objects.iter().filter(|object| is_void_rift(object)) {
+        let mut frontier = void_frontier(game, rift.pos());
+        for _ in 0..SPREAD_TILES {
+            if frontier.is_empty() {
+                break;
+            }
+            let (x, y) = frontier.swap_remove(game.rng.gen_range(0..frontier.len()));
+            game.map[x as usize][y as usize].kind = TileKind::VoidFloor;
+            seen_spreading |= game.fov.is_in_fov(x, y);
+        }
+    }
+    if seen_spreading {
+        game.messages.add("The void spreads...", VOID_COLOR);
+    }
+}
+
+/// the tiles the void can spread to next from the rift at `source`
+fn void_frontier(game: &Game, source: (i32, i32)) -> Vec<(i32, i32)> {
+    let map = &game.map;
+    let in_reach = |(x, y): (i32, i32)| {
+        x >= 0
+            && y >= 0
+            && x < map.len() as i32
+            && y < map[0].len() as i32
+            && (x - source.0).pow(2) + (y - source.1).pow(2) <= SPREAD_RADIUS.pow(2)
+    };
+    let mut tainted = vec![source];
+    let mut seen = vec![source];
+    let mut frontier = vec![];
+    while let Some((x, y)) = tainted.pop() {
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let next = (x + dx, y + dy);
+            if !in_reach(next) || seen.contains(&next) {
+                continue;
+            }
+            seen.push(next);
+            match map[next.0 as usize][next.1 as usize].kind {
+                TileKind::VoidFloor => tainted.push(next),
+                TileKind::Floor | TileKind::Rubble => frontier.push(next),
+                _ => {}
+            }
+        }
+    }
+    frontier
+}
+
+/// Turn the tainted ground within `radius` of (x, y) back into plain floor.
+/// Returns how many tiles were cleansed.
+pub fn cleanse(game: &mut Game, (x, y): (i32, i32), radius: i32) -> usize {
+    let mut cleansed = 0;
+    for (tx, column) in game.map.iter_mut().enumerate() {
+        for (ty, tile) in column.iter_mut().enumerate() {
+            let (dx, dy) = (tx as i32 - x, ty as i32 - y);
+            if tile.kind == TileKind::VoidFloor && dx * dx + dy * dy <= radius * radius {
+                tile.kind = TileKind::Floor;
+                cleansed += 1;
+            }
+        }
+    }
+    cleansed
+}
+
+/// what's left when a rift is destroyed: the void it spread goes with it
+pub(crate) fn void_rift_death(rift: &mut Object, game: &mut Game) {
+    game.messages.add(
+        format!("The {} collapses, and the void around it fades away!", rift.name),
+        VOID_COLOR,
+    );
+    cleanse(game, rift.pos(), SPREAD_RADIUS);
+    rift.char = '*';
+    rift.color = DARK_GREY;
+    rift.blocks = false;
+    rift.fighter = None;
+    rift.name = format!("collapsed {}", rift.name);
+}
+
+/// Open void rifts on the level, more of them the deeper it is, each sitting
+/// in a small patch of tainted ground and well away from the start.
+pub struct VoidRifts;
+
+impl MapBuilder for VoidRifts {
+    fn build(&self, data: &mut BuildData, rng: &mut GameRng) {
+        let rifts = from_dungeon_level(
+            &[
+                Transition { level: 2, value: 1 },
+                Transition { level: 5, value: 2 },
+                Transition { level: 8, value: 3 },
+            ],
+            data.level,
+        );
+        let width = data.map.len() as i32;
+        let height = data.map[0].len() as i32;
+        for _ in 0..rifts {
+            for _ in 0..RIFT_PLACEMENT_TRIES {
+                let (x, y) = (rng.gen_range(0..width), rng.gen_range(0..height));
+                let far_enough = data.start.is_none_or(|(sx, sy)| {
+                    (((x - sx).pow(2) + (y - sy).pow(2)) as f32).sqrt() >= RIFT_MIN_DISTANCE
+                });
+                // a rift blocks its tile, so it goes in the open where it
+                // can't cut a corridor off
+                let open = (x - 1..=x + 1).all(|tx| {
+                    (y - 1..=y + 1).all(|ty| {
+                        tx >= 0
+                            && ty >= 0
+                            && tx < width
+                            && ty < height
+                            && !data.map[tx as usize][ty as usize].blocked()
+                    })
+                });
+                if data.map[x as usize][y as usize].kind != TileKind::Floor
+                    || !open
+                    || !far_enough
+                    || data.exit == Some((x, y))
+                    || data.spawns.iter().any(|object| object.pos() == (x, y))
+                {
+                    continue;
+                }
+
+                for tx in x - RIFT_START_RADIUS..=x + RIFT_START_RADIUS {
+                    for ty in y - RIFT_START_RADIUS..=y + RIFT_START_RADIUS {
+                        let tile = &mut data.map[tx as usize][ty as usize];
+                        if tile.kind == TileKind::Floor {
+                            *tile = Tile::new(TileKind::VoidFloor);
+                        }
+                    }
+                }
+                data.spawns.push(void_rift(x, y));
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use rand::SeedableRng;
+
+use unkindred_hearts::builder::{
+    BuildData, BuilderChain, Doorways, FarthestApart, FirstAndLastRoom, MapBuilder, Pillars,
+    Spawns,
+};
+use unkindred_hearts::game::GameRng;
+use unkindred_hearts::map::Rect;
+use unkindred_hearts::monsters::{load_monsters, MONSTERS_PATH};
+
+/// Two rooms joined by a corridor. Each room is given with its walls, like
+/// `Rect`s are: the one on the left goes from (0, 0) to (10, 10).
+fn two_rooms() -> BuildData {
+    let mut data = BuildData::from_ascii(
+        &[
+            "#################",
+            "#.........#######",
+            "#.........#######",
+            "#.........#######",
+            "#.........#######",
+            "#..............##",
+            "#.........####.##",
+            "#.........####.##",
+            "#.........###...#",
+            "#.........###...#",
+            "#############...#",
+            "#############...#",
+            "#################",
+        ],
+        1,
+    );
+    data.rooms = vec![Rect::new(0, 0, 10, 10), Rect::new(12, 7, 4, 5)];
+    data
+}
+
+/// a base layout that always lays out `two_rooms`
+struct TwoRooms;
+
+impl MapBuilder for TwoRooms {
+    fn build(&self, data: &mut BuildData, _rng: &mut GameRng) {
+        *data = two_rooms();
+    }
+}
+
+/// run a single pass with a fixed seed
+fn run(pass: impl MapBuilder, data: &mut BuildData) {
+    pass.build(data, &mut GameRng::seed_from_u64(3));
+}
+
+fn snapshot(rows: &[&str]) -> String {
+    rows.join("\n")
+}
+
+#[test]
+fn ascii_maps_read_back_the_same() {
+    let rows = ["###", "#.#", "###"];
+    let data = BuildData::from_ascii(&rows, 1);
+    assert!(data.map[0][0].blocked());
+    assert!(!data.map[1][1].blocked());
+    assert_eq!(data.to_ascii(), snapshot(&rows));
+}
+
+#[test]
+fn doorways_are_where_corridors_cut_through_room_walls() {
+    let mut data = two_rooms();
+    run(Doorways, &mut data);
+    assert_eq!(data.doorways, vec![(10, 5), (14, 7)]);
+    assert_eq!(
+        data.to_ascii(),
+        snapshot(&[
+            "#################",
+            "#.........#######",
+            "#.........#######",
+            "#.........#######",
+            "#.........#######",
+            "#.........+....##",
+            "#.........####.##",
+            "#.........####+##",
+            "#.........###...#",
+            "#.........###...#",
+            "#############...#",
+            "#############...#",
+            "#################",
+        ])
+    );
+}
+
+#[test]
+fn pillars_only_go_in_big_rooms() {
+    let mut data = two_rooms();
+    // pillars are put in a room half the time, so try until this seed gets some
+    let mut seed = 0;
+    while !data.map[2][2].blocked() {
+        Pillars.build(&mut data, &mut GameRng::seed_from_u64(seed));
+        seed += 1;
+    }
+    assert_eq!(
+        data.to_ascii(),
+        snapshot(&[
+            "#################",
+            "#.........#######",
+            "#.#.....#.#######",
+            "#.........#######",
+            "#.........#######",
+            "#..............##",
+            "#.........####.##",
+            "#.........####.##",
+            "#.#.....#.###...#",
+            "#.........###...#",
+            "#############...#",
+            "#############...#",
+            "#################",
+        ])
+    );
+}
+
+#[test]
+fn the_player_goes_from_the_first_room_to_the_last() {
+    let mut data = two_rooms();
+    run(FirstAndLastRoom, &mut data);
+    assert_eq!(data.start, Some((5, 5)));
+    assert_eq!(data.exit, Some((14, 9)));
+}
+
+#[test]
+fn the_exit_is_as_far_as_it_gets_from_the_start() {
+    let mut data = BuildData::from_ascii(
+        &[
+            "#######",
+            "#.....#",
+            "#####.#",
+            "#.....#",
+            "#######",
+        ],
+        1,
+    );
+    run(FarthestApart, &mut data);
+    let start = data.start.unwrap();
+    assert!(!data.map[start.0 as usize][start.1 as usize].blocked());
+    // whichever end the player starts at, the exit is at the other one
+    let ends = [(1, 1), (1, 3)];
+    if ends.contains(&start) {
+        assert!(ends.contains(&data.exit.unwrap()));
+        assert_ne!(data.exit, Some(start));
+    }
+}
+
+#[test]
+fn nothing_spawns_in_the_starting_room() {
+    let monsters = load_monsters(MONSTERS_PATH).unwrap();
+    for seed in 0..20 {
+        let chain = BuilderChain::new(TwoRooms)
+            .with(FirstAndLastRoom)
+            .with(Spawns {
+                monsters: &monsters,
+            });
+        let data = chain.build(17, 13, 1, &mut GameRng::seed_from_u64(seed));
+        for object in &data.spawns {
+            assert!(!data.rooms[0].contains(object.pos()));
+            assert!(!data.map[object.x as usize][object.y as usize].blocked());
+        }
+    }
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::*;
+use unkindred_hearts::game::{Command, PlayerAction, PLAYER};
+use unkindred_hearts::monsters::{load_monsters, MONSTERS_PATH};
+use unkindred_hearts::object::Fighter;
+use unkindred_hearts::status::StatusKind;
+
+#[test]
+fn bumping_into_a_monster_attacks_it() {
+    let mut world = world(&["@z."]);
+    play(&mut world, "6");
+
+    // 5 power against 0 defense, and the player stays where they were
+    assert_eq!(hp(&world, "z"), 5);
+    assert_eq!(player_pos(&world), (0, 0));
+    assert!(has_message(&world, "player attacks z for 5 hit points."));
+}
+
+#[test]
+fn monsters_hit_back_through_defense() {
+    let mut world = world(&["@z"]);
+    set_fighter(&mut world, "z", fighter(10, 0, 6));
+    play(&mut world, "5");
+
+    // 6 power against 2 defense: defense takes off a quarter of itself, rounded down
+    assert_eq!(hp(&world, "player"), 24);
+    assert!(has_message(&world, "z attacks player for 6 hit points."));
+
+    set_fighter(&mut world, "player", fighter(24, 8, 5));
+    play(&mut world, "5");
+    assert_eq!(hp(&world, "player"), 20);
+}
+
+#[test]
+fn attacks_that_do_no_damage_say_so() {
+    let mut world = world(&["@z"]);
+    set_fighter(&mut world, "z", fighter(10, 0, 0));
+    play(&mut world, "5");
+
+    assert_eq!(hp(&world, "player"), 30);
+    assert!(has_message(&world, "z attacks player but it has no effect!"));
+}
+
+#[test]
+fn take_damage_only_pays_xp_for_the_kill() {
+    let mut world = world(&["@z"]);
+    let z = find(&world, "z");
+    let (game, objects) = (&mut world.game, &mut world.objects);
+
+    assert_eq!(objects[z].take_damage(4, game), None);
+    assert_eq!(objects[z].fighter.unwrap().hp, 6);
+    // negative damage doesn't heal
+    assert_eq!(objects[z].take_damage(-3, game), None);
+    assert_eq!(objects[z].fighter.unwrap().hp, 6);
+    assert_eq!(objects[z].take_damage(6, game), Some(10));
+}
+
+#[test]
+fn dead_monsters_turn_into_corpses() {
+    let mut world = world(&["@z."]);
+    set_fighter(&mut world, "z", fighter(5, 0, 3));
+    play(&mut world, "6");
+
+    let corpse = &world.objects[find(&world, "Corpse of z")];
+    assert!(!corpse.alive);
+    assert!(!corpse.blocks);
+    assert_eq!(corpse.char, '%');
+    assert!(corpse.fighter.is_none());
+    assert!(corpse.ai.is_none());
+    assert!(has_message(&world, "z dies!"));
+    // the kill was worth its xp
+    assert_eq!(world.objects[PLAYER].fighter.unwrap().xp, 10);
+
+    // corpses don't block the way
+    play(&mut world, "66");
+    assert_eq!(player_pos(&world), (2, 0));
+}
+
+#[test]
+fn the_player_dies_and_the_game_stops() {
+    let mut world = world(&["@z"]);
+    set_fighter(&mut world, "player", fighter(3, 0, 5));
+    set_fighter(&mut world, "z", fighter(10, 0, 9));
+    play(&mut world, "5");
+
+    let player = &world.objects[PLAYER];
+    assert!(!player.alive);
+    assert_eq!(player.char, '%');
+    // the player keeps their fighter (and name), unlike monsters
+    assert!(player.fighter.is_some());
+    assert!(has_message(&world, "Your fragle body smashes into blood and guts!"));
+
+    // nothing can be done anymore
+    assert_eq!(world.step(Command::Move(1, 0)), PlayerAction::DidntTakeTurn);
+    assert_eq!(hp(&world, "z"), 10);
+}
+
+#[test]
+fn a_zombie_stun_costs_the_player_a_turn() {
+    let mut world = world(&["z@.."]);
+    let zombie = load_monsters(MONSTERS_PATH)
+        .unwrap()
+        .into_iter()
+        .find(|monster| monster.name == "Zombie")
+        .unwrap();
+    let mut on_hit = zombie.on_hit.unwrap();
+    assert_eq!(on_hit.effect.kind, StatusKind::Stun);
+    // make sure it lands, but only the once
+    on_hit.chance = 100;
+    set_fighter(&mut world, "z", Fighter { on_hit: Some(on_hit), ..fighter(10, 0, 3) });
+    play(&mut world, "5");
+    assert!(world.objects[PLAYER].has_effect(StatusKind::Stun));
+    let z = find(&world, "z");
+    world.objects[z].fighter.as_mut().unwrap().on_hit = None;
+
+    play(&mut world, "6");
+    assert_eq!(player_pos(&world), (1, 0));
+    assert!(has_message(&world, "You are stunned and can't act!"));
+    assert!(!world.objects[PLAYER].has_effect(StatusKind::Stun));
+
+    play(&mut world, "6");
+    assert_eq!(player_pos(&world), (2, 0));
+}
@@ -0,0 +1,141 @@
+//! Small hand-made worlds for the scenario tests, built from ASCII layouts so
+//! no dungeon generator (or window) is involved.
+#![allow(dead_code)]
+
+use rand::SeedableRng;
+use tcod::colors::*;
+
+use unkindred_hearts::game::{Command, GameRng, World, PLAYER};
+use unkindred_hearts::map::{Map, Tile, TileKind};
+use unkindred_hearts::object::{Ai, DamageKind, DeathCallback, Fighter, Object};
+use unkindred_hearts::schedule::{ACTION_ENERGY, NORMAL_SPEED};
+
+/// the seed every scenario is run with, so random bits come out the same each time
+pub const SEED: u64 = 1234;
+
+/// stats for a plain fighter that hits with `power` and dies as a monster
+pub fn fighter(hp: i32, defense: i32, power: i32) -> Fighter {
+    Fighter {
+        max_hp: hp,
+        hp,
+        defense,
+        power,
+        magic: 0,
+        magic_defense: 0,
+        attack: DamageKind::Physical,
+        on_hit: None,
+        speed: NORMAL_SPEED,
+        xp: 10,
+        on_death: DeathCallback::Monster,
+    }
+}
+
+/// Build a world from rows of text: `@` is the player (30 HP, 2 defense,
+/// 5 power), any letter is a monster named after it, with `fighter(10, 0, 3)`
+/// stats and basic AI, and anything else is the tile drawn with that glyph
+/// (`#` a wall, `.` floor, `+` a closed door, `~` water...). Use
+/// `set_fighter` to change anyone's stats afterwards.
+pub fn world(layout: &[&str]) -> World {
+    let width = layout[0].len();
+    let height = layout.len();
+    let mut map: Map = vec![vec![Tile::empty(); height]; width];
+    let mut player = None;
+    let mut monsters = vec![];
+
+    for (y, row) in layout.iter().enumerate() {
+        assert_eq!(row.len(), width, "every row of the layout must be as wide");
+        for (x, glyph) in row.chars().enumerate() {
+            let (x_pos, y_pos) = (x as i32, y as i32);
+            match glyph {
+                '@' => {
+                    let mut object = Object::new(x_pos, y_pos, '@', "player", YELLOW, true);
+                    object.alive = true;
+                    object.fighter = Some(Fighter {
+                        xp: 0,
+                        on_death: DeathCallback::Player,
+                        ..fighter(30, 2, 5)
+                    });
+                    object.energy = ACTION_ENERGY;
+                    player = Some(object);
+                }
+                glyph if glyph.is_alphabetic() => {
+                    let mut object =
+                        Object::new(x_pos, y_pos, glyph, &glyph.to_string(), WHITE, true);
+                    object.alive = true;
+                    object.fighter = Some(fighter(10, 0, 3));
+                    object.ai = Some(Ai::Basic);
+                    monsters.push(object);
+                }
+                glyph => match TileKind::from_glyph(glyph) {
+                    Some(kind) => map[x][y] = Tile::new(kind),
+                    None => panic!("unknown glyph {:?} in layout", glyph),
+                },
+            }
+        }
+    }
+
+    let mut objects = vec![player.expect("the layout needs an @")];
+    objects.extend(monsters);
+    World::from_parts(map, objects, SEED, GameRng::seed_from_u64(SEED), vec![], vec![])
+}
+
+/// the id of the object with the given name; panics if there isn't one
+pub fn find(world: &World, name: &str) -> usize {
+    world
+        .objects
+        .iter()
+        .position(|object| object.name == name)
+        .unwrap_or_else(|| panic!("no object named {:?}", name))
+}
+
+/// replace the fighter stats of the named object, keeping how it dies
+pub fn set_fighter(world: &mut World, name: &str, stats: Fighter) {
+    let id = find(world, name);
+    let on_death = world.objects[id].fighter.map_or(stats.on_death, |f| f.on_death);
+    world.objects[id].fighter = Some(Fighter { on_death, ..stats });
+}
+
+pub fn hp(world: &World, name: &str) -> i32 {
+    world.objects[find(world, name)]
+        .fighter
+        .map_or(0, |f| f.hp)
+}
+
+pub fn player_pos(world: &World) -> (i32, i32) {
+    world.objects[PLAYER].pos()
+}
+
+/// Play a script of numpad keys as player commands: 1-9 move like the numpad
+/// (5 waits). Spaces are ignored, so scripts can be grouped for readability.
+pub fn play(world: &mut World, script: &str) {
+    for key in script.chars().filter(|c| !c.is_whitespace()) {
+        let command = match key {
+            '8' => Command::Move(0, -1),
+            '2' => Command::Move(0, 1),
+            '4' => Command::Move(-1, 0),
+            '6' => Command::Move(1, 0),
+            '7' => Command::Move(-1, -1),
+            '9' => Command::Move(1, -1),
+            '1' => Command::Move(-1, 1),
+            '3' => Command::Move(1, 1),
+            '5' => Command::Wait,
+            other => panic!("unknown key {:?} in script", other),
+        };
+        world.step(command);
+    }
+}
+
+/// every message in the log, oldest first
+pub fn messages(world: &World) -> Vec<String> {
+    world
+        .game
+        .messages
+        .iter()
+        .map(|message| message.text.clone())
+        .collect()
+}
+
+/// whether any message in the log contains `text`
+pub fn has_message(world: &World, text: &str) -> bool {
+    messages(world).iter().any(|message| message.contains(text))
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::*;
+use unkindred_hearts::game::Command;
+use unkindred_hearts::map::TileKind;
+use unkindred_hearts::object::move_towards;
+
+#[test]
+fn walking_into_a_door_opens_it() {
+    let mut world = world(&[
+        "#####",
+        "#@+z#",
+        "#####",
+    ]);
+    assert!(!world.game.fov.is_in_fov(3, 1));
+    play(&mut world, "6");
+    // opening the door takes the turn, the player stays put
+    assert_eq!(player_pos(&world), (1, 1));
+    assert!(world.game.map[2][1].is_open_door());
+    assert!(has_message(&world, "You open the door"));
+    // and what's behind it can be seen straight away
+    assert!(world.game.fov.is_in_fov(3, 1));
+}
+
+#[test]
+fn doors_can_be_closed_again() {
+    let mut world = world(&[
+        "#####",
+        "#@'.#",
+        "#####",
+    ]);
+    world.step(Command::CloseDoor(1, 0));
+    assert!(world.game.map[2][1].is_closed_door());
+    assert!(!world.game.fov.is_in_fov(3, 1));
+    // there's nothing left to close
+    world.step(Command::CloseDoor(1, 0));
+    assert!(has_message(&world, "no open door"));
+}
+
+#[test]
+fn doors_dont_close_on_whoever_is_standing_in_them() {
+    let mut world = world(&[
+        "#####",
+        "#@z.#",
+        "#####",
+    ]);
+    world.game.map[2][1].kind = TileKind::OpenDoor;
+    world.step(Command::CloseDoor(1, 0));
+    assert!(world.game.map[2][1].is_open_door());
+    assert!(has_message(&world, "Something is in the way"));
+}
+
+#[test]
+fn only_some_monsters_open_doors() {
+    let mut world = world(&[
+        "#######",
+        "#z+..@#",
+        "#######",
+    ]);
+    let z = find(&world, "z");
+    move_towards(z, 5, 1, &mut world.game, &mut world.objects);
+    assert!(world.game.map[2][1].is_closed_door());
+    assert_eq!(world.objects[z].pos(), (1, 1));
+
+    world.objects[z].opens_doors = true;
+    move_towards(z, 5, 1, &mut world.game, &mut world.objects);
+    assert!(world.game.map[2][1].is_open_door());
+    move_towards(z, 5, 1, &mut world.game, &mut world.objects);
+    assert_eq!(world.objects[z].pos(), (2, 1));
+}
+
+#[test]
+fn closing_a_door_out_of_reach_or_off_the_map_does_nothing() {
+    let mut world = world(&[
+        "@'..",
+        "....",
+    ]);
+    let time = world.game.time;
+    for (dx, dy) in [(5, 0), (-1, 0), (0, -1), (-3, -7)] {
+        world.step(Command::CloseDoor(dx, dy));
+    }
+    assert_eq!(world.game.time, time);
+    assert!(world.game.map[1][0].is_open_door());
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::*;
+use unkindred_hearts::game::{Command, World, PLAYER};
+use unkindred_hearts::item::Item;
+
+/// put the item in the player's inventory and have them use it
+fn equip(world: &mut World, item: Item) {
+    world.game.inventory.push(item.spawn(0, 0));
+    let index = world.game.inventory.len() - 1;
+    world.step(Command::UseItem(index, None));
+}
+
+fn equipped(world: &World, index: usize) -> bool {
+    world.game.inventory[index]
+        .equipment
+        .is_some_and(|equipment| equipment.equipped)
+}
+
+#[test]
+fn a_sword_makes_the_player_hit_harder() {
+    let mut world = world(&[
+        "####",
+        "#@z#",
+        "####",
+    ]);
+    equip(&mut world, Item::Sword);
+    assert_eq!(world.objects[PLAYER].power(&world.game), 8);
+    play(&mut world, "6");
+    assert_eq!(hp(&world, "z"), 2);
+}
+
+#[test]
+fn only_one_thing_fits_in_each_slot() {
+    let mut world = world(&[
+        "###",
+        "#@#",
+        "###",
+    ]);
+    equip(&mut world, Item::Helmet);
+    equip(&mut world, Item::Sword);
+    equip(&mut world, Item::Helmet);
+    assert!(!equipped(&world, 0));
+    assert!(equipped(&world, 1));
+    assert!(equipped(&world, 2));
+    assert_eq!(world.objects[PLAYER].defense(&world.game), 3);
+
+    // using it again takes it off
+    world.step(Command::UseItem(2, None));
+    assert!(!equipped(&world, 2));
+    assert_eq!(world.objects[PLAYER].defense(&world.game), 2);
+}
+
+#[test]
+fn taking_off_an_amulet_takes_its_hit_points_with_it() {
+    let mut world = world(&[
+        "###",
+        "#@#",
+        "###",
+    ]);
+    equip(&mut world, Item::AmuletOfWarding);
+    assert_eq!(world.objects[PLAYER].max_hp(&world.game), 40);
+    world.objects[PLAYER].heal(10, &world.game);
+    assert_eq!(hp(&world, "player"), 40);
+
+    world.step(Command::UseItem(0, None));
+    assert_eq!(world.objects[PLAYER].max_hp(&world.game), 30);
+    assert_eq!(hp(&world, "player"), 30);
+}
+
+#[test]
+fn dropped_equipment_comes_off_first() {
+    let mut world = world(&[
+        "###",
+        "#@#",
+        "###",
+    ]);
+    equip(&mut world, Item::AmuletOfWarding);
+    world.objects[PLAYER].heal(10, &world.game);
+    world.step(Command::Drop(0));
+    assert!(world.game.inventory.is_empty());
+    assert_eq!(hp(&world, "player"), 30);
+    let amulet = &world.objects[find(&world, "amulet of warding")];
+    assert!(amulet.equipment.is_some_and(|equipment| !equipment.equipped));
+}
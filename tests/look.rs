@@ -0,0 +1,58 @@
+mod common;
+
+use common::*;
+use unkindred_hearts::look::describe_tile;
+use unkindred_hearts::monsters::{load_monsters, MONSTERS_PATH};
+use unkindred_hearts::status::{StatusEffect, StatusKind};
+
+#[test]
+fn looking_at_a_monster_tells_what_it_is_and_how_hurt_it_looks() {
+    let mut world = world(&[
+        "#####",
+        "#@.z#",
+        "#####",
+    ]);
+    world.monsters = load_monsters(MONSTERS_PATH).unwrap();
+    let z = find(&world, "z");
+    world.objects[z].name = "Zombie".into();
+    world.objects[z].add_effect(StatusEffect {
+        kind: StatusKind::Poison,
+        turns: 3,
+        potency: 1,
+    });
+
+    let lines = describe_tile((3, 1), &world);
+    assert_eq!(lines[0], "Zombie: unhurt");
+    assert!(lines[1].contains("shambles"), "{:?}", lines);
+    assert_eq!(lines[2], "It is poisoned (3 turns).");
+    assert_eq!(lines[3], "Terrain: bare stone floor.");
+
+    let estimates = [
+        (8, "barely scratched"),
+        (5, "wounded"),
+        (3, "badly wounded"),
+        (1, "almost dead"),
+    ];
+    for (hp, estimate) in estimates {
+        set_fighter(&mut world, "Zombie", fighter(hp, 0, 3));
+        world.objects[z].fighter.as_mut().unwrap().max_hp = 10;
+        assert_eq!(describe_tile((3, 1), &world)[0], format!("Zombie: {}", estimate));
+    }
+}
+
+#[test]
+fn tiles_out_of_view_only_tell_what_is_remembered() {
+    let mut world = world(&[
+        "#######",
+        "#@#.z.#",
+        "#######",
+    ]);
+    let lines = describe_tile((4, 1), &world);
+    assert_eq!(lines, ["You haven't seen what's there."]);
+
+    world.game.map[4][1].explored = true;
+    let lines = describe_tile((4, 1), &world);
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].contains("can't see it from here"), "{:?}", lines);
+    assert!(!lines[0].contains('z'));
+}
@@ -0,0 +1,96 @@
+use rand::SeedableRng;
+
+use unkindred_hearts::builder::BuildData;
+use unkindred_hearts::game::{GameRng, MAP_HEIGHT, MAP_WIDTH};
+use unkindred_hearts::map::{builder_for, distances_from, map_style, MapStyle};
+use unkindred_hearts::monsters::{load_monsters, MONSTERS_PATH};
+use unkindred_hearts::prefab::{load_prefabs, PREFABS_PATH};
+
+/// build a whole floor in the given style
+fn build(style: MapStyle, seed: u64, level: u32) -> BuildData {
+    let monsters = load_monsters(MONSTERS_PATH).unwrap();
+    let prefabs = load_prefabs(PREFABS_PATH, &monsters).unwrap();
+    let mut rng = GameRng::seed_from_u64(seed);
+    let chain = builder_for(style, &monsters, &prefabs);
+    chain.build(MAP_WIDTH, MAP_HEIGHT, level, &mut rng)
+}
+
+/// check that every floor tile, and everything on the map, can be walked to from the start
+fn assert_connected(data: &BuildData) {
+    let map = &data.map;
+    let start = data.start.expect("the map has no start");
+    assert!(!map[start.0 as usize][start.1 as usize].blocked());
+    let distances = distances_from(map, start);
+    for (x, column) in map.iter().enumerate() {
+        for (y, tile) in column.iter().enumerate() {
+            assert!(
+                tile.blocked() || distances[x][y].is_some(),
+                "({}, {}) can't be reached",
+                x,
+                y
+            );
+        }
+    }
+    let exit = data.exit.expect("the map has no exit");
+    assert!(distances[exit.0 as usize][exit.1 as usize].is_some());
+    for object in &data.spawns {
+        assert!(distances[object.x as usize][object.y as usize].is_some());
+    }
+}
+
+#[test]
+fn caves_can_be_walked_end_to_end() {
+    for seed in 0..20 {
+        let data = build(MapStyle::Caves, seed, 5);
+        let map = &data.map;
+
+        assert_connected(&data);
+        // the edge of the map is solid rock
+        assert!(map[0].iter().chain(map.last().unwrap()).all(|tile| tile.blocked()));
+        assert!(map.iter().all(|column| column[0].blocked() && column.last().unwrap().blocked()));
+    }
+}
+
+#[test]
+fn bsp_rooms_are_all_joined_up() {
+    for seed in 0..20 {
+        let data = build(MapStyle::Bsp, seed, 1);
+        let map = &data.map;
+
+        assert_connected(&data);
+        // the rooms are spread over the whole map, so there's floor in every corner of it
+        let floor_in = |xs: std::ops::Range<usize>, ys: std::ops::Range<usize>| {
+            xs.flat_map(|x| ys.clone().map(move |y| (x, y)))
+                .any(|(x, y)| !map[x][y].blocked())
+        };
+        let (half_w, half_h) = (MAP_WIDTH as usize / 2, MAP_HEIGHT as usize / 2);
+        assert!(floor_in(0..half_w, 0..half_h));
+        assert!(floor_in(half_w..MAP_WIDTH as usize, 0..half_h));
+        assert!(floor_in(0..half_w, half_h..MAP_HEIGHT as usize));
+        assert!(floor_in(half_w..MAP_WIDTH as usize, half_h..MAP_HEIGHT as usize));
+    }
+}
+
+#[test]
+fn random_rooms_are_all_joined_up() {
+    for seed in 0..20 {
+        assert_connected(&build(MapStyle::Rooms, seed, 1));
+    }
+}
+
+#[test]
+fn floors_come_out_the_same_for_the_same_seed() {
+    for style in [MapStyle::Rooms, MapStyle::Bsp, MapStyle::Caves] {
+        assert_eq!(build(style, 99, 5).to_ascii(), build(style, 99, 5).to_ascii());
+    }
+}
+
+#[test]
+fn the_first_levels_are_never_caves() {
+    let mut rng = GameRng::seed_from_u64(7);
+    for _ in 0..50 {
+        assert_ne!(map_style(1, &mut rng), MapStyle::Caves);
+        assert_ne!(map_style(2, &mut rng), MapStyle::Caves);
+        assert_eq!(map_style(8, &mut rng), MapStyle::Caves);
+    }
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::*;
+use tcod::colors::*;
+use unkindred_hearts::messages::Messages;
+
+#[test]
+fn the_same_message_over_and_over_is_counted_instead() {
+    let mut world = world(&[
+        "######",
+        "#@z..#",
+        "######",
+    ]);
+    play(&mut world, "555");
+    let last = world.game.messages.iter().last().unwrap();
+    assert_eq!(last.count, 3);
+    assert_eq!(last.display(), format!("{} x3", last.text));
+    assert_eq!(messages(&world).len(), 1);
+
+    // anything else in between starts a new line
+    world.game.messages.add("You wait.", WHITE);
+    play(&mut world, "5");
+    assert_eq!(world.game.messages.iter().last().unwrap().count, 1);
+    assert_eq!(messages(&world).len(), 3);
+}
+
+#[test]
+fn messages_remember_the_turn_they_were_said_on() {
+    let mut world = world(&[
+        "#####",
+        "#@..#",
+        "#####",
+    ]);
+    world.game.messages.add("First.", WHITE);
+    play(&mut world, "55");
+    world.game.messages.add("Second.", WHITE);
+    play(&mut world, "5");
+    world.game.messages.add("First.", WHITE);
+
+    let turns: Vec<_> = world.game.messages.iter().map(|message| message.turn).collect();
+    assert_eq!(turns, [0, 2, 3]);
+}
+
+#[test]
+fn the_log_keeps_only_so_many_and_can_be_searched() {
+    let mut messages = Messages::new();
+    for i in 0..10 {
+        messages.add(format!("Message {}.", i), WHITE);
+    }
+    messages.add("The zombie dies!", ORANGE);
+    messages.set_max_messages(4);
+    let texts: Vec<_> = messages.iter().map(|message| message.text.as_str()).collect();
+    assert_eq!(texts, ["Message 7.", "Message 8.", "Message 9.", "The zombie dies!"]);
+
+    messages.add("Message 10.", WHITE);
+    assert_eq!(messages.len(), 4);
+    let found: Vec<_> = messages.search("ZOMBIE").map(|message| message.text.as_str()).collect();
+    assert_eq!(found, ["The zombie dies!"]);
+    assert_eq!(messages.search("").count(), 4);
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use common::*;
+use tcod::colors::WHITE;
+use unkindred_hearts::game::{Command, PLAYER};
+use unkindred_hearts::object::{is_blocked, move_towards, Object};
+use unkindred_hearts::status::{StatusEffect, StatusKind};
+
+#[test]
+fn walls_stop_the_player() {
+    let mut world = world(&[
+        "#####",
+        "#@..#",
+        "#####",
+    ]);
+    play(&mut world, "66 6 8");
+    assert_eq!(player_pos(&world), (3, 1));
+    play(&mut world, "44 1");
+    assert_eq!(player_pos(&world), (1, 1));
+}
+
+#[test]
+fn the_player_moves_diagonally() {
+    let mut world = world(&[
+        "@..",
+        "...",
+        "...",
+    ]);
+    play(&mut world, "33 7");
+    assert_eq!(player_pos(&world), (1, 1));
+}
+
+#[test]
+fn walls_and_blocking_objects_block() {
+    let mut world = world(&[
+        "#@z",
+        "...",
+    ]);
+    let (map, objects) = (&world.game.map, &world.objects);
+    assert!(is_blocked(0, 0, map, objects));
+    assert!(is_blocked(2, 0, map, objects));
+    assert!(is_blocked(1, 0, map, objects));
+    assert!(!is_blocked(1, 1, map, objects));
+
+    // once dead, the monster is out of the way
+    let z = find(&world, "z");
+    world.objects[z].fighter.as_mut().unwrap().hp = 1;
+    play(&mut world, "6");
+    assert!(!is_blocked(2, 0, &world.game.map, &world.objects));
+}
+
+#[test]
+fn monsters_walk_around_walls() {
+    let mut world = world(&[
+        "......",
+        ".####.",
+        "z#..#.",
+        ".#@.#.",
+        ".....#",
+    ]);
+    let z = find(&world, "z");
+    let target = player_pos(&world);
+    for _ in 0..3 {
+        move_towards(z, target.0, target.1, &mut world.game, &mut world.objects);
+    }
+    // down the left side and round the corner of the wall, next to the player
+    assert_eq!(world.objects[z].pos(), (1, 4));
+}
+
+#[test]
+fn monsters_step_straight_at_the_target_without_a_path() {
+    let mut world = world(&[
+        "z.#@",
+        "..#.",
+    ]);
+    let z = find(&world, "z");
+    move_towards(z, 3, 0, &mut world.game, &mut world.objects);
+    assert_eq!(world.objects[z].pos(), (1, 0));
+    // the wall stops it there
+    move_towards(z, 3, 0, &mut world.game, &mut world.objects);
+    assert_eq!(world.objects[z].pos(), (1, 0));
+}
+
+#[test]
+fn monsters_chase_the_player_they_can_see() {
+    let mut world = world(&["@....z"]);
+    play(&mut world, "5");
+    assert_eq!(world.objects[find(&world, "z")].pos(), (4, 0));
+    play(&mut world, "555");
+    assert_eq!(world.objects[find(&world, "z")].pos(), (1, 0));
+    assert_eq!(hp(&world, "player"), 30);
+}
+
+#[test]
+fn a_confused_player_staggers_about_without_hitting_themselves() {
+    let mut world = world(&[
+        "#####",
+        "#...#",
+        "#.@.#",
+        "#...#",
+        "#####",
+    ]);
+    world.objects[PLAYER].add_effect(StatusEffect {
+        kind: StatusKind::Confusion,
+        turns: 100,
+        potency: 0,
+    });
+    // with this many tries, some of them stay on the spot
+    play(&mut world, &"8".repeat(40));
+    assert_eq!(hp(&world, "player"), 30);
+
+    // as does asking to move nowhere
+    let time = world.game.time;
+    world.step(Command::Move(0, 0));
+    assert!(world.game.time > time);
+}
+
+#[test]
+fn only_the_floors_own_stairs_lead_down() {
+    let mut world = world(&[
+        "#####",
+        "#@..#",
+        "#####",
+    ]);
+    // something else that happens to be called that doesn't count
+    let decoy = Object::new(1, 1, '<', "stairs", WHITE, false);
+    world.objects.push(decoy);
+    world.step(Command::Descend);
+    assert_eq!(world.game.dungeon_level, 1);
+
+    world.game.stairs = Some(player_pos(&world));
+    world.step(Command::Descend);
+    assert_eq!(world.game.dungeon_level, 2);
+    // the new floor has stairs of its own, away from the player
+    let stairs = world.game.stairs.unwrap();
+    assert_ne!(stairs, player_pos(&world));
+    assert!(world.objects.iter().any(|object| object.pos() == stairs && object.char == '<'));
+}
@@ -0,0 +1,102 @@
+use rand::SeedableRng;
+
+use unkindred_hearts::builder::{BuildData, MapBuilder};
+use unkindred_hearts::game::GameRng;
+use unkindred_hearts::item::Item;
+use unkindred_hearts::map::is_connected;
+use unkindred_hearts::monsters::{load_monsters, MonsterTemplate, MONSTERS_PATH};
+use unkindred_hearts::prefab::{parse_prefabs, Prefab, Vaults};
+
+/// a small shrine, always placed, with a guardian and some loot
+const SHRINE: &str = r######"[
+    (
+        name: "shrine",
+        layout: [
+            "#####",
+            "#s!s#",
+            "#...#",
+            "##+##",
+        ],
+        legend: {
+            's': Monster("Baby Spider"),
+            '!': Item(Heal),
+        },
+        chance: 100,
+        min_depth: 1,
+    ),
+]"######;
+
+fn monsters() -> Vec<MonsterTemplate> {
+    load_monsters(MONSTERS_PATH).unwrap()
+}
+
+fn shrine() -> Vec<Prefab> {
+    parse_prefabs(SHRINE, &monsters()).unwrap()
+}
+
+/// run the vaults pass with a fixed seed
+fn stamp(prefabs: &[Prefab], data: &mut BuildData) {
+    let monsters = monsters();
+    let pass = Vaults {
+        prefabs,
+        monsters: &monsters,
+    };
+    pass.build(data, &mut GameRng::seed_from_u64(5));
+}
+
+fn count(data: &BuildData, name: &str) -> usize {
+    data.spawns.iter().filter(|o| o.name == name).count()
+}
+
+#[test]
+fn prefabs_that_make_no_sense_are_refused() {
+    let monsters = monsters();
+    let unknown_glyph = SHRINE.replace("#...#", "#.x.#");
+    let error = parse_prefabs(&unknown_glyph, &monsters).unwrap_err();
+    assert!(error.contains("'x'"), "{}", error);
+
+    let unknown_monster = SHRINE.replace("Baby Spider", "Giant Spider");
+    let error = parse_prefabs(&unknown_monster, &monsters).unwrap_err();
+    assert!(error.contains("Giant Spider"), "{}", error);
+
+    let no_entrance = SHRINE.replace("##+##", "#####");
+    let error = parse_prefabs(&no_entrance, &monsters).unwrap_err();
+    assert!(error.contains("no entrance"), "{}", error);
+}
+
+#[test]
+fn a_vault_in_rock_gets_a_corridor_to_the_level() {
+    let mut rows = vec!["#".repeat(20); 12];
+    rows[1] = format!("#...{}", "#".repeat(16));
+    rows[2] = rows[1].clone();
+    let rows: Vec<&str> = rows.iter().map(|row| row.as_str()).collect();
+    let mut data = BuildData::from_ascii(&rows, 1);
+    stamp(&shrine(), &mut data);
+
+    assert_eq!(count(&data, "Baby Spider"), 2);
+    assert_eq!(count(&data, "healing potion"), 1);
+    assert!(data.spawns.iter().any(|o| o.item == Some(Item::Heal)));
+    assert_eq!(data.doorways.len(), 1);
+    assert!(is_connected(&data.map, (1, 1)), "\n{}", data.to_ascii());
+}
+
+#[test]
+fn a_vault_in_open_floor_can_be_walked_around() {
+    let mut rows = vec![format!("#{}#", ".".repeat(12)); 10];
+    rows[0] = "#".repeat(14);
+    rows[9] = rows[0].clone();
+    let rows: Vec<&str> = rows.iter().map(|row| row.as_str()).collect();
+    let mut data = BuildData::from_ascii(&rows, 1);
+    stamp(&shrine(), &mut data);
+
+    assert_eq!(count(&data, "Baby Spider"), 2);
+    assert!(is_connected(&data.map, (1, 1)), "\n{}", data.to_ascii());
+}
+
+#[test]
+fn vaults_are_left_out_where_they_dont_fit() {
+    let mut data = BuildData::from_ascii(&["#####", "#...#", "#####"], 1);
+    stamp(&shrine(), &mut data);
+    assert!(data.spawns.is_empty());
+    assert_eq!(data.to_ascii(), ["#####", "#...#", "#####"].join("\n"));
+}
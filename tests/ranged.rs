@@ -0,0 +1,134 @@
+mod common;
+
+use common::*;
+use unkindred_hearts::game::{Command, World, PLAYER};
+use unkindred_hearts::item::Item;
+use unkindred_hearts::object::Ai;
+use unkindred_hearts::fov::line;
+use unkindred_hearts::ranged::line_of_fire;
+
+/// give the player a bow, on, and a bundle of arrows
+fn arm_with_bow(world: &mut World) {
+    world.game.inventory.push(Item::Bow.spawn(0, 0));
+    world.game.inventory.push(Item::Arrows.spawn(0, 0));
+    world.step(Command::UseItem(0, None));
+}
+
+#[test]
+fn projectiles_stop_at_walls_and_at_whatever_is_in_the_way() {
+    let world = world(&[
+        "########",
+        "#@.z..##",
+        "#...#..#",
+        "########",
+    ]);
+    assert_eq!(line((1, 1), (4, 4)), vec![(2, 2), (3, 3), (4, 4)]);
+
+    let (path, hit) = line_of_fire((1, 1), (5, 1), &world.game.map, &world.objects);
+    assert_eq!(path, vec![(2, 1), (3, 1)]);
+    assert_eq!(hit, Some(find(&world, "z")));
+
+    let (path, hit) = line_of_fire((1, 2), (6, 2), &world.game.map, &world.objects);
+    assert_eq!(path, vec![(2, 2), (3, 2)]);
+    assert_eq!(hit, None);
+}
+
+#[test]
+fn the_player_shoots_arrows_from_a_bow() {
+    let mut world = world(&[
+        "#######",
+        "#@...z#",
+        "#######",
+    ]);
+    arm_with_bow(&mut world);
+    // it came closer while the bow was put on
+    let z = find(&world, "z");
+    let (x, y) = world.objects[z].pos();
+    world.step(Command::Fire(x, y));
+    assert_eq!(hp(&world, "z"), 4);
+    assert_eq!(world.game.inventory[1].count, 11);
+    assert_eq!(world.game.shots.len(), 1);
+    assert_eq!(world.game.shots[0].path.last(), Some(&(x, y)));
+}
+
+#[test]
+fn nothing_is_shot_without_a_bow_or_arrows() {
+    let mut world = world(&[
+        "#######",
+        "#@...z#",
+        "#######",
+    ]);
+    world.game.inventory.push(Item::Arrows.spawn(0, 0));
+    world.step(Command::Fire(5, 1));
+    assert!(has_message(&world, "nothing to shoot with"));
+
+    world.game.inventory.clear();
+    world.game.inventory.push(Item::Bow.spawn(0, 0));
+    world.step(Command::UseItem(0, None));
+    let time = world.game.time;
+    world.step(Command::Fire(5, 1));
+    assert!(has_message(&world, "no arrows left"));
+    assert_eq!(world.game.time, time);
+    assert_eq!(hp(&world, "z"), 10);
+}
+
+#[test]
+fn thrown_knives_land_where_they_stop_and_stack_again() {
+    let mut world = world(&[
+        "#######",
+        "#@...z#",
+        "#######",
+    ]);
+    world.game.inventory.push(Item::ThrowingKnives.spawn(0, 0));
+    world.step(Command::UseItem(0, Some((5, 1))));
+    assert_eq!(hp(&world, "z"), 5);
+    assert_eq!(world.game.inventory[0].count, 4);
+    let knife = find(&world, "throwing knives");
+    assert_eq!(world.objects[knife].pos(), (5, 1));
+
+    // picking it back up puts it on the pile
+    world.objects[knife].set_pos(1, 1);
+    world.step(Command::PickUp);
+    assert_eq!(world.game.inventory.len(), 1);
+    assert_eq!(world.game.inventory[0].count, 5);
+}
+
+#[test]
+fn throwing_the_last_knife_leaves_none_behind() {
+    let mut world = world(&[
+        "#######",
+        "#@...z#",
+        "#######",
+    ]);
+    let mut knife = Item::ThrowingKnives.spawn(0, 0);
+    knife.count = 1;
+    world.game.inventory.push(knife);
+    world.step(Command::UseItem(0, Some((5, 1))));
+    assert!(world.game.inventory.is_empty());
+    assert_eq!(hp(&world, "z"), 5);
+
+    // so there's nothing left to throw
+    world.step(Command::UseItem(0, Some((5, 1))));
+    assert_eq!(hp(&world, "z"), 5);
+    let knives = world.objects.iter().filter(|object| object.name == "throwing knives");
+    assert_eq!(knives.count(), 1);
+}
+
+#[test]
+fn ranged_monsters_shoot_from_afar_and_back_away_up_close() {
+    let mut world = world(&[
+        "#########",
+        "#@....a.#",
+        "#########",
+    ]);
+    let archer = find(&world, "a");
+    world.objects[archer].ai = Some(Ai::Ranged { range: 6 });
+    play(&mut world, "5");
+    assert_eq!(world.objects[archer].pos(), (6, 1));
+    assert_eq!(hp(&world, "player"), 27);
+    assert!(has_message(&world, "a shoots player"));
+
+    world.objects[PLAYER].set_pos(5, 1);
+    play(&mut world, "5");
+    assert_eq!(world.objects[archer].pos(), (7, 1));
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use common::*;
+use unkindred_hearts::game::{Command, World};
+use unkindred_hearts::monsters::{load_monsters, MONSTERS_PATH};
+use unkindred_hearts::prefab::{load_prefabs, PREFABS_PATH};
+use unkindred_hearts::replay::{Recorder, Replay};
+
+/// a replay file of its own for each test, so they can run side by side
+fn replay_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("unkindred-hearts-{}-{}", name, std::process::id()))
+}
+
+/// a generated world from `seed`, with the real game data
+fn generated_world(seed: u64) -> World {
+    let monsters = load_monsters(MONSTERS_PATH).unwrap();
+    let prefabs = load_prefabs(PREFABS_PATH, &monsters).unwrap();
+    World::new(seed, monsters, prefabs)
+}
+
+/// everything a save would keep, to compare two worlds by
+fn snapshot(world: &World) -> (String, String) {
+    (
+        serde_json::to_string(&world.game).unwrap(),
+        serde_json::to_string(&world.objects).unwrap(),
+    )
+}
+
+#[test]
+fn replaying_a_seed_and_its_commands_plays_the_exact_same_game() {
+    let path = replay_path("same-game");
+    let mut world = generated_world(SEED);
+    world.recorder = Some(Recorder::create(&path, SEED).unwrap());
+    play(&mut world, "6666 2222 4444 8888 5555 3333 9999 1111 7777 5555 6262 4848");
+    world.recorder = None;
+
+    let replay = Replay::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(replay.seed, SEED);
+    assert_eq!(replay.commands.len(), 48);
+
+    let mut again = generated_world(replay.seed);
+    for &command in &replay.commands {
+        again.step(command);
+    }
+    assert!(world.game.time > 0);
+    assert_eq!(snapshot(&again), snapshot(&world));
+}
+
+#[test]
+fn a_replay_cut_off_mid_line_keeps_every_whole_command() {
+    let path = replay_path("cut-off");
+    let mut recorder = Recorder::create(&path, SEED).unwrap();
+    for command in [Command::Move(1, 0), Command::Wait, Command::PickUp] {
+        recorder.record(command).unwrap();
+    }
+    drop(recorder);
+    // as if the game crashed while writing the next one
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    write!(file, "{{\"Mo").unwrap();
+    drop(file);
+
+    let replay = Replay::load(&path).unwrap();
+    assert_eq!(replay.commands, [Command::Move(1, 0), Command::Wait, Command::PickUp]);
+
+    // but a broken line anywhere else is a mistake
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    writeln!(file, "\n\"Wait\"").unwrap();
+    drop(file);
+    let error = Replay::load(&path).err().unwrap().to_string();
+    fs::remove_file(&path).unwrap();
+    assert!(error.contains("line 5"), "{}", error);
+}
+
+#[test]
+fn resuming_a_replay_drops_what_the_save_never_saw() {
+    let path = replay_path("resume");
+    let mut recorder = Recorder::create(&path, SEED).unwrap();
+    for _ in 0..5 {
+        recorder.record(Command::Wait).unwrap();
+    }
+    drop(recorder);
+
+    // another run's replay, or one shorter than the save, can't be picked up
+    assert!(Recorder::resume(&path, SEED + 1, 3).is_err());
+    assert!(Recorder::resume(&path, SEED, 6).is_err());
+
+    let mut recorder = Recorder::resume(&path, SEED, 3).unwrap();
+    recorder.record(Command::PickUp).unwrap();
+    drop(recorder);
+    let replay = Replay::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(replay.commands, [Command::Wait, Command::Wait, Command::Wait, Command::PickUp]);
+}
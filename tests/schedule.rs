@@ -0,0 +1,104 @@
+use unkindred_hearts::schedule::{run_until_ready, Action, Actors, ACTION_ENERGY, NORMAL_SPEED};
+
+/// a stand-in for the world: actors that only keep track of when they acted
+struct Timeline {
+    speeds: Vec<Option<i32>>,
+    energy: Vec<i32>,
+    /// what each actor does whenever it gets to act
+    actions: Vec<Action>,
+    acted: Vec<usize>,
+    time: u64,
+    turns: u32,
+}
+
+impl Timeline {
+    fn new(speeds: &[Option<i32>]) -> Self {
+        Timeline {
+            speeds: speeds.to_vec(),
+            energy: vec![0; speeds.len()],
+            actions: vec![Action::Move; speeds.len()],
+            acted: vec![],
+            time: 0,
+            turns: 0,
+        }
+    }
+
+    /// actor 0 plays the player: it does `action`, then waits for its next go
+    fn player_does(&mut self, action: Action) {
+        self.energy[0] -= action.cost();
+        run_until_ready(self, 0);
+    }
+
+    fn times_acted(&self, id: usize) -> usize {
+        self.acted.iter().filter(|&&actor| actor == id).count()
+    }
+}
+
+impl Actors for Timeline {
+    fn count(&self) -> usize {
+        self.speeds.len()
+    }
+
+    fn speed(&self, id: usize) -> Option<i32> {
+        self.speeds[id]
+    }
+
+    fn energy(&mut self, id: usize) -> &mut i32 {
+        &mut self.energy[id]
+    }
+
+    fn act(&mut self, id: usize) -> Action {
+        self.acted.push(id);
+        self.actions[id]
+    }
+
+    fn time(&mut self) -> &mut u64 {
+        &mut self.time
+    }
+
+    fn end_turn(&mut self) {
+        self.turns += 1;
+    }
+}
+
+#[test]
+fn speed_decides_how_often_actors_go() {
+    let normal = Some(NORMAL_SPEED);
+    let mut timeline = Timeline::new(&[normal, Some(200), normal, Some(50), None]);
+    timeline.energy[0] = ACTION_ENERGY;
+
+    for _ in 0..4 {
+        timeline.player_does(Action::Move);
+    }
+    assert_eq!(timeline.times_acted(1), 8);
+    assert_eq!(timeline.times_acted(2), 4);
+    assert_eq!(timeline.times_acted(3), 2);
+    // things without a speed never act
+    assert_eq!(timeline.times_acted(4), 0);
+    assert_eq!(timeline.turns, 4);
+}
+
+#[test]
+fn cheap_actions_give_others_less_time() {
+    let mut timeline = Timeline::new(&[Some(NORMAL_SPEED), Some(NORMAL_SPEED)]);
+    timeline.energy[0] = ACTION_ENERGY;
+
+    timeline.player_does(Action::Quaff);
+    assert_eq!(timeline.times_acted(1), 0);
+    timeline.player_does(Action::Quaff);
+    assert_eq!(timeline.times_acted(1), 1);
+    assert_eq!(timeline.turns, 1);
+}
+
+#[test]
+fn actors_doing_cheap_things_act_more_often() {
+    let mut timeline = Timeline::new(&[Some(NORMAL_SPEED), Some(NORMAL_SPEED)]);
+    timeline.energy[0] = ACTION_ENERGY;
+    timeline.actions[1] = Action::Quaff;
+
+    timeline.player_does(Action::Wait);
+    assert_eq!(timeline.times_acted(1), 1);
+    // its first quaff only took half its energy, so it's halfway to the next one
+    timeline.player_does(Action::Wait);
+    assert_eq!(timeline.times_acted(1), 3);
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::*;
+use unkindred_hearts::game::Command;
+use unkindred_hearts::item::Item;
+use unkindred_hearts::targeting::{visible_enemies, Targeting};
+
+#[test]
+fn tab_goes_through_the_enemies_in_reach_closest_first() {
+    let world = world(&[
+        "############",
+        "#@..b.....a#",
+        "#..c########",
+        "#####d######",
+    ]);
+    let targeting = Targeting::item(Item::Confuse).unwrap();
+    let names: Vec<_> = visible_enemies(&targeting, &world.game, &world.objects)
+        .into_iter()
+        .map(|id| world.objects[id].name.clone())
+        .collect();
+    // `a` is out of range and `d` can't be seen
+    assert_eq!(names, ["c", "b"]);
+}
+
+#[test]
+fn the_preview_shows_the_line_of_fire_and_the_area_hit() {
+    let world = world(&[
+        "#########",
+        "#@..z...#",
+        "#.......#",
+        "#########",
+    ]);
+    let knives = Targeting::item(Item::ThrowingKnives).unwrap();
+    // the knife would stop at `z`, and only hit the one tile
+    assert_eq!(knives.path((6, 1), &world.game, &world.objects), [(2, 1), (3, 1), (4, 1)]);
+    assert_eq!(knives.area((6, 1), &world.game), [(6, 1)]);
+    assert!(!knives.allows((1, 1), &world.game, &world.objects));
+
+    let fireball = Targeting::item(Item::Fireball).unwrap();
+    assert!(fireball.path((4, 1), &world.game, &world.objects).is_empty());
+    let area = fireball.area((5, 1), &world.game);
+    assert!(area.contains(&(3, 2)) && area.contains(&(7, 2)));
+    assert!(!area.contains(&(1, 1)));
+}
+
+#[test]
+fn shots_can_be_aimed_as_far_as_the_bow_reaches() {
+    let mut world = world(&[
+        "############",
+        "#@.........#",
+        "############",
+    ]);
+    assert_eq!(
+        Targeting::fire(&world.game, &world.objects),
+        Err("You have nothing to shoot with.")
+    );
+    world.game.inventory.push(Item::Bow.spawn(0, 0));
+    world.step(Command::UseItem(0, None));
+    assert_eq!(
+        Targeting::fire(&world.game, &world.objects),
+        Err("You have no arrows left.")
+    );
+    world.game.inventory.push(Item::Arrows.spawn(0, 0));
+    let targeting = Targeting::fire(&world.game, &world.objects).unwrap();
+    assert!(targeting.allows((9, 1), &world.game, &world.objects));
+    assert!(!targeting.allows((10, 1), &world.game, &world.objects));
+}
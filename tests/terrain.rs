@@ -0,0 +1,58 @@
+mod common;
+
+use common::*;
+use unkindred_hearts::game::PLAYER;
+use unkindred_hearts::path::find_path;
+
+#[test]
+fn lava_burns_whoever_steps_in_it() {
+    let mut world = world(&[
+        "#####",
+        "#@=.#",
+        "#####",
+    ]);
+    play(&mut world, "6");
+    assert_eq!(player_pos(&world), (2, 1));
+    assert_eq!(hp(&world, "player"), 24);
+    assert!(has_message(&world, "burned by the lava"));
+}
+
+#[test]
+fn wading_through_water_takes_twice_as_long() {
+    let mut world = world(&[
+        "######",
+        "#.@~.#",
+        "######",
+    ]);
+    play(&mut world, "4");
+    let step = world.game.time;
+    // one step on the floor, then one into the water
+    play(&mut world, "66");
+    assert_eq!(player_pos(&world), (3, 1));
+    assert_eq!(world.game.time - step, step * 3);
+}
+
+#[test]
+fn chasms_can_be_seen_over_but_not_crossed() {
+    let mut world = world(&[
+        "#####",
+        "#@:z#",
+        "#####",
+    ]);
+    assert!(world.game.fov.is_in_fov(3, 1));
+    play(&mut world, "6");
+    assert_eq!(player_pos(&world), (1, 1));
+}
+
+#[test]
+fn paths_go_around_lava_when_they_can() {
+    let world = world(&[
+        "#######",
+        "#.....#",
+        "#@===.#",
+        "#######",
+    ]);
+    let path = find_path(world.objects[PLAYER].pos(), (5, 2), &world.game.map, &[], false, 100)
+        .expect("there is a way round");
+    assert!(path.iter().all(|&(x, y)| y == 1 || (x, y) == (5, 2)), "{:?}", path);
+}
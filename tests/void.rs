@@ -0,0 +1,113 @@
+mod common;
+
+use common::*;
+use unkindred_hearts::game::{Command, World};
+use unkindred_hearts::item::Item;
+use unkindred_hearts::map::TileKind;
+use unkindred_hearts::object::Ai;
+use unkindred_hearts::void::void_rift;
+
+fn void_tiles(world: &World) -> usize {
+    world
+        .game
+        .map
+        .iter()
+        .flatten()
+        .filter(|tile| tile.kind == TileKind::VoidFloor)
+        .count()
+}
+
+#[test]
+fn the_void_spreads_from_its_rift_but_not_through_walls() {
+    let mut world = world(&[
+        "############",
+        "#@.#.......#",
+        "############",
+    ]);
+    world.objects.push(void_rift(8, 1));
+    world.game.map[8][1].kind = TileKind::VoidFloor;
+    play(&mut world, "5555 5555");
+    assert_eq!(void_tiles(&world), 3);
+    play(&mut world, &"5".repeat(40));
+    assert_eq!(void_tiles(&world), 7);
+    // the wall keeps it out of the player's side
+    assert!((1..=2).all(|x| world.game.map[x][1].kind == TileKind::Floor));
+}
+
+#[test]
+fn monsters_standing_in_the_void_hit_harder() {
+    let mut world = world(&[
+        "####",
+        "#@z#",
+        "####",
+    ]);
+    play(&mut world, "5");
+    assert_eq!(hp(&world, "player"), 27);
+    world.game.map[2][1].kind = TileKind::VoidFloor;
+    play(&mut world, "5");
+    assert_eq!(hp(&world, "player"), 22);
+}
+
+#[test]
+fn archers_standing_in_the_void_shoot_harder_too() {
+    let mut world = world(&[
+        "#######",
+        "#@...a#",
+        "#######",
+    ]);
+    let archer = find(&world, "a");
+    world.objects[archer].ai = Some(Ai::Ranged { range: 6 });
+    world.game.map[5][1].kind = TileKind::VoidFloor;
+    play(&mut world, "5");
+    assert!(has_message(&world, "a shoots player for 5 hit points."));
+    assert_eq!(hp(&world, "player"), 25);
+}
+
+#[test]
+fn standing_in_the_void_slowly_wears_the_player_down() {
+    let mut world = world(&[
+        "#####",
+        "#@..#",
+        "#####",
+    ]);
+    world.game.map[1][1].kind = TileKind::VoidFloor;
+    play(&mut world, "555555");
+    assert_eq!(hp(&world, "player"), 28);
+    assert!(has_message(&world, "The void gnaws at you"));
+}
+
+#[test]
+fn destroying_a_rift_drives_its_void_away() {
+    let mut world = world(&[
+        "######",
+        "#@,,,#",
+        "######",
+    ]);
+    world.objects.push(void_rift(2, 1));
+    play(&mut world, "6666");
+    assert!(has_message(&world, "collapses"));
+    assert_eq!(void_tiles(&world), 0);
+    // what's left of it doesn't get in the way
+    play(&mut world, "6");
+    assert_eq!(player_pos(&world), (2, 1));
+}
+
+#[test]
+fn a_scroll_of_purification_cleanses_the_ground_around_the_player() {
+    let mut world = world(&[
+        "##########",
+        "#@,,.....#",
+        "##########",
+    ]);
+    world.game.map[8][1].kind = TileKind::VoidFloor;
+    world.game.inventory.push(Item::Purify.spawn(0, 0));
+    world.step(Command::UseItem(0, None));
+    assert!(world.game.inventory.is_empty());
+    // only what's in reach is cleansed
+    assert_eq!(void_tiles(&world), 1);
+
+    // with nothing left in reach, the next scroll isn't wasted
+    world.game.inventory.push(Item::Purify.spawn(0, 0));
+    world.step(Command::UseItem(0, None));
+    assert_eq!(world.game.inventory.len(), 1);
+}